@@ -1,85 +1,99 @@
-use super::controller::{Command, Controller};
-use super::game_tree::GameTree;
-use crate::evaluation::evaluate_material;
+use super::controller::{bot_accepts_draw, Command, Controller};
+use super::search::{time_budget, Search, SearchConfig, SearchLimits, MAX_DEPTH};
+use crate::evaluation::Evaluator;
+use crate::model::clock::Clock;
 use crate::model::game_state::GameState;
 use crate::model::movement::Movement;
 use crate::view::{AsciiDisplay, GameDisplay};
 
-use std::collections::BinaryHeap;
+use std::time::Duration;
 
-/// Controller that uses Minimax algorithm with alpha-beta pruning
+/// Controller that uses Minimax algorithm with alpha-beta pruning and iterative deepening.
 #[derive(Clone)]
 pub struct AlphaBetaBot {
-    depth: u32,
-    tree: GameTree,
+    limits: SearchLimits,
+    search: Search,
 }
 
 impl AlphaBetaBot {
+    /// Searches up to the given depth, unless the game clock requires moving sooner.
     pub fn new(depth: u32) -> Self {
+        Self::with_limits(SearchLimits::depth(depth))
+    }
+
+    /// Searches as deep as possible within the given time per movement.
+    pub fn with_time_limit(time: Duration) -> Self {
+        Self::with_limits(SearchLimits {
+            depth: MAX_DEPTH,
+            time: Some(time),
+            nodes: None,
+        })
+    }
+
+    /// Searches as deep as possible within the given number of nodes per movement.
+    pub fn with_node_limit(nodes: u64) -> Self {
+        Self::with_limits(SearchLimits {
+            depth: MAX_DEPTH,
+            time: None,
+            nodes: Some(nodes),
+        })
+    }
+
+    fn with_limits(limits: SearchLimits) -> Self {
         Self {
-            tree: GameTree {
-                score: 0,
-                game_state: GameState::new(),
-                children: BinaryHeap::new(),
-            },
-            depth,
+            limits,
+            search: Search::new(limits),
         }
     }
+
+    pub fn set_search_config(&mut self, config: SearchConfig) {
+        self.search.set_config(config);
+    }
+
+    /// Evaluator used to score the positions at the end of the search, `FullEvaluator` by default.
+    pub fn set_evaluator(&mut self, evaluator: impl Evaluator + 'static) {
+        self.search.set_evaluator(evaluator);
+    }
+
+    /// Size of the transposition table in megabytes. A size of 0 disables the table.
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.search.set_hash_size(size_mb);
+    }
 }
 
 impl AlphaBetaBot {
-    fn update_tree(&mut self, game_state: &GameState) {
-        if self.tree.children.len() == 0 {
-            self.tree = GameTree {
-                score: evaluate_material(game_state, game_state.player_to_move),
-                game_state: game_state.clone(),
-                children: BinaryHeap::new(),
-            };
-            return;
+    fn search_limits(&self, game_state: &GameState, clock: Option<&Clock>) -> SearchLimits {
+        let mut limits = self.limits;
+        if let Some(clock) = clock {
+            let budget = time_budget(clock, game_state.player_to_move);
+            limits.time = Some(limits.time.map_or(budget, |time| time.min(budget)));
         }
-
-        // look for corresponding tree node
-        while let Some(child) = self.tree.children.pop() {
-            if child.game_state == *game_state {
-                self.tree = child;
-                return;
-            }
-        }
-        AsciiDisplay.display_game(game_state);
-        dbg!(&game_state.last_move);
-        println!("Unexpected movement");
-        // movement was not in the tree
-        self.tree = GameTree {
-            score: -evaluate_material(game_state, game_state.player_to_move),
-            game_state: game_state.clone(),
-            children: BinaryHeap::new(),
-        };
+        limits
     }
 
-    fn choose_move(&mut self, game_state: &GameState) -> Movement {
-        if *game_state != self.tree.game_state {
-            self.update_tree(game_state);
-        }
-        self.tree
-            .alphabeta_search(self.depth, 255, -20000, 20000)
-            .unwrap();
-        let c = self.tree.children.pop();
-        match c {
+    fn choose_move(&mut self, game_state: &GameState, clock: Option<&Clock>) -> Movement {
+        self.search.set_limits(self.search_limits(game_state, clock));
+        let result = self.search.run(game_state);
+        match result.best_move {
+            Some(movement) => movement,
             None => {
                 AsciiDisplay.display_game(game_state);
-                panic!();
+                panic!("No movements available");
             }
-            _ => {}
         }
-        let chosen_child = c.unwrap();
-        let chosen_movement = chosen_child.game_state.last_move.clone().unwrap();
-        self.tree = chosen_child;
-        return chosen_movement;
     }
 }
 
 impl Controller for AlphaBetaBot {
-    fn choose_command(&mut self, game_state: &mut GameState) -> super::controller::Command {
-        return Command::Move(self.choose_move(game_state));
+    fn accept_draw(&mut self, game_state: &GameState) -> bool {
+        bot_accepts_draw(self.search.evaluator(), game_state)
+    }
+
+    fn choose_command(
+        &mut self,
+        game_state: &mut GameState,
+        clock: Option<&Clock>,
+    ) -> super::controller::Command {
+        return Command::Move(self.choose_move(game_state, clock));
     }
 }
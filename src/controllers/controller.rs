@@ -1,3 +1,6 @@
+use crate::evaluation::Evaluator;
+use crate::game::GameResult;
+use crate::model::clock::Clock;
 use crate::model::game_state::GameState;
 use crate::model::movement::Movement;
 use serde::{Deserialize, Serialize};
@@ -7,7 +10,19 @@ pub enum Command {
     Undo,
     AcceptUndo,
     Resign,
+    /// The player's flag fell while the controller waited for the command, or on the clock of a
+    /// remote opponent.
+    Timeout,
+    ClaimDraw,
+    OfferDraw,
+    /// Reply to a draw offer of the opponent.
+    AcceptDraw,
+    /// Reply to a draw offer of the opponent.
+    DeclineDraw,
     Save,
+    SavePgn,
+    /// Show the pieces of the player that the opponent attacks.
+    ShowThreats,
     Move(Movement),
 }
 
@@ -16,5 +31,21 @@ pub trait Controller {
     fn accept_undo(&mut self) -> bool {
         return true;
     }
-    fn choose_command(&mut self, game_state: &mut GameState) -> Command;
+    fn accept_draw(&mut self, _game_state: &GameState) -> bool {
+        false
+    }
+    /// `clock` is the remaining time of the players, if the game is played with a clock.
+    /// The game only checks the flag once the command is returned, so controllers that wait for
+    /// the player should give up with `Command::Timeout` when the player's time runs out.
+    fn choose_command(&mut self, game_state: &mut GameState, clock: Option<&Clock>) -> Command;
+    /// Called for both players when the game ends.
+    fn game_over(&mut self, _game_state: &GameState, _result: &GameResult) {}
+}
+
+/// Draw policy of the computer players: they accept the opponent's offer when their evaluation
+/// of the position says that they are losing.
+pub fn bot_accepts_draw(evaluator: &dyn Evaluator, game_state: &GameState) -> bool {
+    // the offer is made on the opponent's turn
+    let color = game_state.player_to_move.get_opponent_color();
+    evaluator.evaluate(game_state, color) < 0
 }
@@ -1,4 +1,4 @@
-use crate::evaluation::{evaluate_game_over, evaluate_material};
+use crate::evaluation::{evaluate_game_over, Evaluator};
 use crate::model::movement::Movement;
 use crate::model::{game_state::GameState, piece::PieceType};
 
@@ -16,6 +16,8 @@ pub struct GameTree {
     pub game_state: GameState,
     pub score: i32,
     pub children: BinaryHeap<GameTree>,
+    /// A position that already occurred is scored as a draw, since the players can keep repeating it.
+    is_repetition: bool,
 }
 
 impl PartialEq for GameTree {
@@ -38,6 +40,18 @@ impl Ord for GameTree {
 }
 
 impl GameTree {
+    /// Node without children. The previous positions are not kept in its game state,
+    /// since the repetitions are found with the hashes of the positions on the path to the node.
+    pub fn new(mut game_state: GameState, score: i32) -> Self {
+        game_state.position_history = Vec::new();
+        Self {
+            game_state,
+            score,
+            children: BinaryHeap::new(),
+            is_repetition: false,
+        }
+    }
+
     pub fn get_depth(&self) -> u32 {
         let mut depth_heap = BinaryHeap::new();
         for child in &self.children {
@@ -51,10 +65,7 @@ impl GameTree {
     }
 
     fn is_king_capture(movement: &Option<Movement>, game_state: &GameState) -> bool {
-        if let Some(Movement::Normal {
-            from: _,
-            to: [x, y],
-        }) = movement
+        if let Some(Movement::Normal { to: [x, y], .. }) = movement
         {
             match game_state.board[*x][*y] {
                 Some(piece) if piece.piece_type == PieceType::King => {
@@ -69,7 +80,8 @@ impl GameTree {
         false
     }
 
-    fn expand_node(&mut self) -> Result<(), ()> {
+    /// Add a child for each movement. The path has the hashes of the positions before this node.
+    fn expand_node(&mut self, path: &[u64], evaluator: &dyn Evaluator) -> Result<(), ()> {
         let mut possible_movements = generate_movements_for_player_ignoring_check(
             &self.game_state,
             self.game_state.player_to_move,
@@ -78,15 +90,16 @@ impl GameTree {
         possible_movements.shuffle(&mut rng);
         for movement in possible_movements {
             let game_state2 = self.game_state.clone_and_move(movement);
-            let score = evaluate_material(&game_state2, self.game_state.player_to_move);
+            let score = evaluator.evaluate(&game_state2, self.game_state.player_to_move);
             if Self::is_king_capture(&game_state2.last_move, &self.game_state) {
                 return Err(());
             } else {
-                self.children.push(GameTree {
-                    score,
-                    game_state: game_state2,
-                    children: BinaryHeap::new(),
-                });
+                let mut child = GameTree::new(game_state2, score);
+                child.is_repetition = path.contains(&child.game_state.zobrist_hash());
+                if child.is_repetition {
+                    child.score = 0;
+                }
+                self.children.push(child);
             }
         }
         // update score
@@ -97,12 +110,18 @@ impl GameTree {
     }
 
     #[allow(dead_code)]
-    pub fn dfs(&mut self, depth_limit: u32, branch_limit: u32) -> Result<(), ()> {
+    pub fn dfs(
+        &mut self,
+        depth_limit: u32,
+        branch_limit: u32,
+        path: &mut Vec<u64>,
+        evaluator: &dyn Evaluator,
+    ) -> Result<(), ()> {
         if depth_limit == 0 {
-            self.score = -evaluate_material(&self.game_state, self.game_state.player_to_move);
+            self.score = -evaluator.evaluate(&self.game_state, self.game_state.player_to_move);
             return Ok(());
         } else if depth_limit == 1 {
-            match self.expand_node() {
+            match self.expand_node(path, evaluator) {
                 Err(()) => {
                     return Err(());
                 }
@@ -114,7 +133,7 @@ impl GameTree {
                 }
             }
         } else if self.children.len() == 0 {
-            match self.expand_node() {
+            match self.expand_node(path, evaluator) {
                 Ok(()) => {}
                 Err(()) => {
                     return Err(());
@@ -123,9 +142,12 @@ impl GameTree {
         }
         let mut reordered_children = BinaryHeap::new();
         let mut branch_count = 0;
+        path.push(self.game_state.zobrist_hash());
         while let Some(mut child) = self.children.pop() {
-            if branch_count < branch_limit {
-                match child.dfs(depth_limit - 1, branch_limit) {
+            if child.is_repetition {
+                reordered_children.push(child);
+            } else if branch_count < branch_limit {
+                match child.dfs(depth_limit - 1, branch_limit, path, evaluator) {
                     Ok(()) => {
                         if child.children.len() == 0 {
                             if depth_limit > 1 && is_game_over(&child.game_state) {
@@ -147,6 +169,7 @@ impl GameTree {
                 reordered_children.push(child);
             }
         }
+        path.pop();
         self.children = reordered_children;
         // update score
         if let Some(child) = self.children.peek() {
@@ -157,15 +180,25 @@ impl GameTree {
         return Ok(());
     }
 
-    pub fn expand_leaves(&mut self, branch_limit: u32) -> Result<(), ()> {
+    /// Expand the leaves of the most promising branches. The path has the hashes
+    /// of the positions before this node, since the last irreversible movement or earlier.
+    pub fn expand_leaves(
+        &mut self,
+        branch_limit: u32,
+        path: &mut Vec<u64>,
+        evaluator: &dyn Evaluator,
+    ) -> Result<(), ()> {
         if self.children.len() == 0 {
-            return self.expand_node();
+            return self.expand_node(path, evaluator);
         } else {
             let mut reordered_children = BinaryHeap::new();
             let mut branch_count = 0;
+            path.push(self.game_state.zobrist_hash());
             while let Some(mut child) = self.children.pop() {
-                if branch_count < branch_limit {
-                    match child.expand_leaves(25) {
+                if child.is_repetition {
+                    reordered_children.push(child);
+                } else if branch_count < branch_limit {
+                    match child.expand_leaves(25, path, evaluator) {
                         Ok(()) => {
                             if child.children.len() == 0 {
                                 child.score = -evaluate_game_over(
@@ -185,6 +218,7 @@ impl GameTree {
                     reordered_children.push(child);
                 }
             }
+            path.pop();
             self.children = reordered_children;
             // update score
             if let Some(child) = self.children.peek() {
@@ -193,58 +227,4 @@ impl GameTree {
             return Ok(());
         }
     }
-
-    pub fn alphabeta_search(
-        &mut self,
-        depth_limit: u32,
-        branch_limit: u32,
-        alpha: i32,
-        beta: i32,
-    ) -> Result<i32, ()> {
-        if depth_limit == 0 {
-            // If we've reached the maximum depth or a leaf node, evaluate the node and return its score.
-            self.score = -evaluate_material(&self.game_state, self.game_state.player_to_move);
-            return Ok(self.score);
-        }
-        self.children = BinaryHeap::new();
-        match self.expand_node() {
-            Ok(()) => {}
-            Err(()) => {
-                return Err(());
-            }
-        }
-        if self.children.is_empty() {
-            // No possibilities for next moves (game over)
-            self.score = -evaluate_game_over(&self.game_state, self.game_state.player_to_move);
-            return Ok(self.score);
-        }
-
-        let mut best_score = alpha;
-
-        // Create a new BinaryHeap to hold the updated child nodes
-        let mut updated_children = BinaryHeap::new();
-
-        while let Some(mut child) = self.children.pop() {
-            // Recursively call alphabeta on the child nodes with negated alpha and beta for the opposite player
-            let Ok(_) = child.alphabeta_search(depth_limit - 1, branch_limit, -beta, -best_score) else {
-                continue;
-            };
-
-            // Update the best_score using the maximum value (`.max()`)
-            best_score = best_score.max(child.score);
-
-            // Push the updated child back into the new BinaryHeap
-            updated_children.push(child);
-            if best_score >= beta {
-                // Prune the remaining nodes as the current player has already found a better move.
-                break;
-            }
-        }
-
-        // Replace the children with the updated BinaryHeap
-        self.children = updated_children;
-
-        self.score = -best_score;
-        Ok(best_score)
-    }
 }
@@ -1,7 +1,9 @@
-use crate::controllers::controller::Controller;
+use crate::controllers::controller::{Command, Controller};
+use crate::menu::{accept_draw_menu, read_line, read_line_timeout};
+use crate::model::clock::Clock;
 use crate::model::game_state::GameState;
 
-use std::io;
+use std::time::Instant;
 
 use super::command_parser::CommandParser;
 
@@ -9,15 +11,31 @@ use super::command_parser::CommandParser;
 pub struct LocalHuman;
 
 impl Controller for LocalHuman {
-    fn choose_command(&mut self, game_state: &mut GameState) -> super::controller::Command {
+    fn accept_draw(&mut self, _game_state: &GameState) -> bool {
+        accept_draw_menu()
+    }
+
+    fn choose_command(&mut self, game_state: &mut GameState, clock: Option<&Clock>) -> Command {
         let parser = CommandParser;
-        let mut buffer: String = String::new();
-        let stdin = io::stdin();
-        let _ = stdin.read_line(&mut buffer);
-        let Ok(cmd) = parser.parse_command(buffer.as_str(), game_state) else {
-            println!("Invalid move");
-            return self.choose_command(game_state);
-        };
-        return cmd;
+        let deadline =
+            clock.map(|clock| Instant::now() + clock.get_remaining(game_state.player_to_move));
+        loop {
+            let buffer = match deadline {
+                Some(deadline) => {
+                    let Some(line) =
+                        read_line_timeout(deadline.saturating_duration_since(Instant::now()))
+                    else {
+                        // the player's flag fell while typing
+                        return Command::Timeout;
+                    };
+                    line
+                }
+                None => read_line(),
+            };
+            match parser.parse_command(buffer.as_str(), game_state) {
+                Ok(cmd) => return cmd,
+                Err(error) => println!("Invalid move: {}", error),
+            }
+        }
     }
 }
@@ -1,10 +1,10 @@
-use crate::evaluation::evaluate_material;
-use crate::model::{game_state::GameState, movement::Movement};
+use crate::evaluation::{replace_evaluator, Evaluator, MaterialEvaluator};
+use crate::model::{clock::Clock, game_state::GameState, movement::Movement};
 use crate::view::{AsciiDisplay, GameDisplay};
 
-use std::collections::BinaryHeap;
+use std::sync::Arc;
 
-use super::controller::{Command, Controller};
+use super::controller::{bot_accepts_draw, Command, Controller};
 use super::game_tree::GameTree;
 
 /// Controller that uses Minimax algorithm.
@@ -12,29 +12,33 @@ use super::game_tree::GameTree;
 pub struct MinimaxBot {
     depth: u32,
     tree: GameTree,
+    evaluator: Arc<dyn Evaluator>,
 }
 
 impl MinimaxBot {
     pub fn new(depth: u32) -> Self {
         Self {
-            tree: GameTree {
-                score: 0,
-                game_state: GameState::new(),
-                children: BinaryHeap::new(),
-            },
+            tree: GameTree::new(GameState::new(), 0),
             depth,
+            evaluator: Arc::new(MaterialEvaluator),
         }
     }
+
+    /// Evaluator used to score the leaves of the game tree, `MaterialEvaluator` by default.
+    pub fn set_evaluator(&mut self, evaluator: impl Evaluator + 'static) {
+        replace_evaluator(&mut self.evaluator, evaluator, || {
+            self.tree.children.clear()
+        });
+    }
 }
 
 impl MinimaxBot {
     fn update_tree(&mut self, game_state: &GameState) {
         if self.tree.children.len() == 0 {
-            self.tree = GameTree {
-                score: evaluate_material(game_state, game_state.player_to_move),
-                game_state: game_state.clone(),
-                children: BinaryHeap::new(),
-            };
+            let score = self
+                .evaluator
+                .evaluate(game_state, game_state.player_to_move);
+            self.tree = GameTree::new(game_state.clone(), score);
             return;
         }
 
@@ -49,19 +53,23 @@ impl MinimaxBot {
         dbg!(&game_state.last_move);
         println!("Unexpected movement");
         // movement was not in the tree
-        self.tree = GameTree {
-            score: evaluate_material(game_state, game_state.player_to_move),
-            game_state: game_state.clone(),
-            children: BinaryHeap::new(),
-        };
+        let score = self
+            .evaluator
+            .evaluate(game_state, game_state.player_to_move);
+        self.tree = GameTree::new(game_state.clone(), score);
     }
 
     fn choose_move(&mut self, game_state: &GameState) -> Movement {
         if *game_state != self.tree.game_state {
             self.update_tree(game_state);
         }
+        // positions of the game before the root of the tree, which can be repeated in the tree
+        let mut path = game_state.position_history.clone();
         for _ in 0..2 {
-            match self.tree.expand_leaves(255) {
+            match self
+                .tree
+                .expand_leaves(255, &mut path, self.evaluator.as_ref())
+            {
                 Ok(_) => {}
                 Err(_) => {
                     println!("Invalid state:");
@@ -70,13 +78,21 @@ impl MinimaxBot {
             };
         }
         while self.tree.get_depth() < self.depth {
-            match self.tree.expand_leaves(25) {
+            let depth = self.tree.get_depth();
+            match self
+                .tree
+                .expand_leaves(25, &mut path, self.evaluator.as_ref())
+            {
                 Ok(_) => {}
                 Err(_) => {
                     println!("Invalid state:");
                     AsciiDisplay.display_game(game_state);
                 }
             };
+            if self.tree.get_depth() == depth {
+                // the deepest leaves are repetitions or finished games
+                break;
+            }
         }
         let chosen_child = self.tree.children.pop().unwrap();
         let chosen_movement = chosen_child.game_state.last_move.clone().unwrap();
@@ -86,7 +102,15 @@ impl MinimaxBot {
 }
 
 impl Controller for MinimaxBot {
-    fn choose_command(&mut self, game_state: &mut GameState) -> super::controller::Command {
+    fn accept_draw(&mut self, game_state: &GameState) -> bool {
+        bot_accepts_draw(self.evaluator.as_ref(), game_state)
+    }
+
+    fn choose_command(
+        &mut self,
+        game_state: &mut GameState,
+        _clock: Option<&Clock>,
+    ) -> super::controller::Command {
         return Command::Move(self.choose_move(game_state));
     }
 }
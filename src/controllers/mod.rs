@@ -4,5 +4,8 @@ pub mod controller;
 mod game_tree;
 pub mod local_human;
 pub mod minimax;
+pub mod move_ordering;
 pub mod random_bot;
 pub mod remote_human;
+pub mod search;
+pub mod transposition;
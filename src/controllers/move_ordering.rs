@@ -0,0 +1,186 @@
+use crate::evaluation::piece_value;
+use crate::model::{game_state::GameState, movement::Movement, piece::PieceType};
+
+use super::search::MAX_DEPTH;
+
+const HASH_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const KILLER_SCORES: [i32; 2] = [90_000, 80_000];
+const COUNTERMOVE_SCORE: i32 = 70_000;
+/// History scores are kept below the countermove score.
+const MAX_HISTORY: i32 = 60_000;
+
+/// Heuristics that can be switched off, to compare their effect on the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderingConfig {
+    /// Captures first, the most valuable victim first and then the least valuable attacker.
+    pub mvv_lva: bool,
+    /// Quiet movements that caused a cutoff at the same ply in a sibling node.
+    pub killer_moves: bool,
+    /// Quiet movements that caused cutoffs anywhere in the search, weighted by depth.
+    pub history_heuristic: bool,
+    /// Quiet movement that refuted the same previous movement of the opponent.
+    pub countermoves: bool,
+}
+
+impl Default for OrderingConfig {
+    fn default() -> Self {
+        Self {
+            mvv_lva: true,
+            killer_moves: true,
+            history_heuristic: true,
+            countermoves: true,
+        }
+    }
+}
+
+/// Origin and destination squares as indexes from 0 to 63.
+fn squares(movement: &Movement) -> Option<(usize, usize)> {
+    match movement {
+        Movement::Normal { from, to, .. } => Some((from[0] * 8 + from[1], to[0] * 8 + to[1])),
+        _ => None,
+    }
+}
+
+/// Tables learned during the search to try the best movements first, so that more branches are pruned.
+#[derive(Clone)]
+pub struct MoveOrdering {
+    pub config: OrderingConfig,
+    killer_moves: Vec<[Option<Movement>; 2]>,
+    /// Indexed by color, origin and destination.
+    history: Vec<[[i32; 64]; 64]>,
+    /// Indexed by the origin and destination of the previous movement.
+    countermoves: Vec<Option<Movement>>,
+}
+
+impl MoveOrdering {
+    pub fn new(config: OrderingConfig) -> Self {
+        Self {
+            config,
+            killer_moves: vec![[None, None]; MAX_DEPTH as usize + 1],
+            history: vec![[[0; 64]; 64]; 2],
+            countermoves: vec![None; 64 * 64],
+        }
+    }
+
+    /// Prepares the tables for a new search: killer moves are specific to the position,
+    /// while the history is kept with less weight.
+    pub fn new_search(&mut self) {
+        for killers in self.killer_moves.iter_mut() {
+            *killers = [None, None];
+        }
+        for row in self.history.iter_mut().flatten() {
+            for score in row.iter_mut() {
+                *score /= 2;
+            }
+        }
+    }
+
+    /// Sorts the movements from the most to the least promising.
+    pub fn sort(
+        &self,
+        movements: &mut [Movement],
+        game_state: &GameState,
+        hash_move: &Option<Movement>,
+        ply: u32,
+    ) {
+        movements.sort_by_cached_key(|movement| -self.score(movement, game_state, hash_move, ply));
+    }
+
+    fn score(
+        &self,
+        movement: &Movement,
+        game_state: &GameState,
+        hash_move: &Option<Movement>,
+        ply: u32,
+    ) -> i32 {
+        if hash_move.as_ref() == Some(movement) {
+            return HASH_MOVE_SCORE;
+        }
+        if self.config.mvv_lva {
+            if let Some(score) = mvv_lva(movement, game_state) {
+                return CAPTURE_SCORE + score;
+            }
+        }
+        if self.config.killer_moves {
+            let killers = &self.killer_moves[ply as usize];
+            for (killer, score) in killers.iter().zip(KILLER_SCORES) {
+                if killer.as_ref() == Some(movement) {
+                    return score;
+                }
+            }
+        }
+        if self.config.countermoves {
+            if let Some(countermove) = self.get_countermove(game_state) {
+                if countermove == movement {
+                    return COUNTERMOVE_SCORE;
+                }
+            }
+        }
+        if self.config.history_heuristic {
+            if let Some((from, to)) = squares(movement) {
+                return self.history[game_state.player_to_move as usize][from][to];
+            }
+        }
+        0
+    }
+
+    fn get_countermove(&self, game_state: &GameState) -> Option<&Movement> {
+        let (from, to) = squares(game_state.last_move.as_ref()?)?;
+        self.countermoves[from * 64 + to].as_ref()
+    }
+
+    /// Remembers a quiet movement that caused a beta cutoff.
+    pub fn update(&mut self, movement: &Movement, game_state: &GameState, depth: u32, ply: u32) {
+        if is_capture(movement, game_state) {
+            // captures are already searched early
+            return;
+        }
+        let killers = &mut self.killer_moves[ply as usize];
+        if killers[0].as_ref() != Some(movement) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(movement.clone());
+        }
+        if let Some((from, to)) = squares(movement) {
+            let history = &mut self.history[game_state.player_to_move as usize][from][to];
+            *history = (*history + (depth * depth) as i32).min(MAX_HISTORY);
+        }
+        if let Some((from, to)) = game_state.last_move.as_ref().and_then(squares) {
+            self.countermoves[from * 64 + to] = Some(movement.clone());
+        }
+    }
+}
+
+fn is_capture(movement: &Movement, game_state: &GameState) -> bool {
+    mvv_lva(movement, game_state).is_some()
+}
+
+/// Score of a capture: the victim's value dominates, then cheaper attackers are preferred.
+/// Promotions are scored as capturing the new piece. None for quiet movements.
+fn mvv_lva(movement: &Movement, game_state: &GameState) -> Option<i32> {
+    let Movement::Normal {
+        from,
+        to,
+        promotion,
+    } = movement
+    else {
+        return None;
+    };
+    let attacker = game_state.board[from[0]][from[1]]?;
+    let is_pawn = attacker.piece_type == PieceType::Pawn;
+    let victim = match game_state.board[to[0]][to[1]] {
+        Some(victim) => Some(victim.piece_type),
+        None if is_pawn && game_state.en_passant_square == Some(*to) => Some(PieceType::Pawn),
+        None => None,
+    };
+    let promotion = match promotion {
+        Some(piece_type) => Some(*piece_type),
+        None if is_pawn && (to[0] == 0 || to[0] == 7) => Some(PieceType::Queen),
+        None => None,
+    };
+    if victim.is_none() && promotion.is_none() {
+        return None;
+    }
+    let gain = victim.map_or(0, piece_value) + promotion.map_or(0, piece_value);
+    Some(gain * 100 - piece_value(attacker.piece_type))
+}
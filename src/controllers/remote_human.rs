@@ -1,8 +1,10 @@
-use std::io::{Read, Write};
+use std::io::{self, ErrorKind, Read, Write};
 use std::net::{TcpListener, TcpStream};
 
 use crate::controllers::controller::{Command, Controller};
+use crate::game::{GameResult, SavedGame, Termination};
 use crate::menu::accept_undo_menu;
+use crate::model::clock::Clock;
 use crate::model::game_state::GameState;
 use crate::model::piece::Color;
 
@@ -12,12 +14,15 @@ pub struct RemoteHuman {
     pub color: Color,
     dont_send_last_move: bool,
     undo_accepted: bool,
+    /// The opponent offered a draw, and the local player's reply is still to be sent.
+    draw_offered: bool,
 }
 
 const GET_COLOR: &str = "GET_COLOR";
 const GET_STATE: &str = "GET_STATE";
 const UNDO_MSG: &str = "UNDO_MSG";
 const ACCEPT_UNDO: &str = "ACCEPT_UNDO";
+const DRAW_OFFER: &str = "DRAW_OFFER";
 
 impl RemoteHuman {
     /// Create controller and wait for connection
@@ -33,6 +38,7 @@ impl RemoteHuman {
                 stream: stream.unwrap(),
                 dont_send_last_move: true,
                 undo_accepted: false,
+                draw_offered: false,
             };
             println!("Connection established!");
             return rh;
@@ -52,6 +58,7 @@ impl RemoteHuman {
             stream,
             dont_send_last_move: true,
             undo_accepted: false,
+            draw_offered: false,
         };
         println!("Connection established!");
         rh.color = rh.get_color().get_opponent_color();
@@ -59,32 +66,54 @@ impl RemoteHuman {
     }
 
     pub fn receive_message(&mut self) -> String {
+        self.read_message().expect("Failed to read message.")
+    }
+
+    /// Wait for a message until the remote player's time runs out, or return `None`.
+    fn receive_message_in_time(&mut self, clock: Option<&Clock>) -> Option<String> {
+        let Some(clock) = clock else {
+            return Some(self.receive_message());
+        };
+        let remaining = clock.get_remaining(self.color);
+        if remaining.is_zero() {
+            return None;
+        }
+        let _ = self.stream.set_read_timeout(Some(remaining));
+        let result = self.read_message();
+        let _ = self.stream.set_read_timeout(None);
+        match result {
+            Ok(message) => Some(message),
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                None
+            }
+            Err(error) => panic!("Failed to read message: {}", error),
+        }
+    }
+
+    fn read_message(&mut self) -> io::Result<String> {
         let mut buffer = [0u8; 1024];
-        let n = self
-            .stream
-            .read(&mut buffer)
-            .expect("Failed to read message.");
+        let n = self.stream.read(&mut buffer)?;
         if n == 0 {
             println!("Peer disconnected.");
-            return String::new();
+            return Ok(String::new());
         }
-        return String::from_utf8_lossy(&buffer[..n]).to_string();
+        return Ok(String::from_utf8_lossy(&buffer[..n]).to_string());
     }
 
-    /// Ask what is the current game state
-    pub fn get_game_state(&mut self) -> GameState {
+    /// Ask what is the current game state and clock
+    pub fn get_game_state(&mut self) -> SavedGame {
         let _ = self.stream.write(GET_STATE.as_bytes());
         let response = self.receive_message();
-        let Ok(game_state) = serde_json::from_str::<GameState>(response.as_str()) else {
+        let Ok(saved_game) = serde_json::from_str::<SavedGame>(response.as_str()) else {
             println!("Received invalid response from server");
             panic!()
         };
-        if game_state.player_to_move == self.color {
+        if saved_game.game_state.player_to_move == self.color {
             self.dont_send_last_move = true;
         } else {
             self.dont_send_last_move = false;
         }
-        return game_state;
+        return saved_game;
     }
 
     /// Ask host what is the player's color
@@ -99,14 +128,23 @@ impl RemoteHuman {
     }
 
     /// Handle messages that don't contain movements
-    pub fn handle_message(&mut self, received_message: String, game_state: &GameState) {
+    pub fn handle_message(
+        &mut self,
+        received_message: String,
+        game_state: &GameState,
+        clock: Option<&Clock>,
+    ) {
         if received_message == GET_STATE {
             if game_state.player_to_move == self.color {
                 self.dont_send_last_move = true;
             } else {
                 self.dont_send_last_move = false;
             }
-            let send_msg = serde_json::to_string(game_state).unwrap();
+            let saved_game = SavedGame {
+                game_state: game_state.deepclone(),
+                clock: clock.cloned(),
+            };
+            let send_msg = serde_json::to_string(&saved_game).unwrap();
             let _ = self.stream.write(send_msg.as_bytes());
         } else if received_message == GET_COLOR {
             let send_msg = serde_json::to_string(&self.color).unwrap();
@@ -125,10 +163,10 @@ impl RemoteHuman {
     }
 
     /// Handle initial requests for color and game state
-    pub fn reply_to_initial_messages(&mut self, game_state: &GameState) {
+    pub fn reply_to_initial_messages(&mut self, game_state: &GameState, clock: Option<&Clock>) {
         for _ in 0..2 {
             let received_message = self.receive_message();
-            self.handle_message(received_message, game_state);
+            self.handle_message(received_message, game_state, clock);
         }
     }
 }
@@ -144,7 +182,24 @@ impl Controller for RemoteHuman {
         }
     }
 
-    fn choose_command(&mut self, game_state: &mut GameState) -> super::controller::Command {
+    fn accept_draw(&mut self, _game_state: &GameState) -> bool {
+        let _ = self.stream.write(DRAW_OFFER.as_bytes());
+        let reply = self.receive_message();
+        matches!(serde_json::from_str(&reply), Ok(Command::AcceptDraw))
+    }
+
+    fn choose_command(
+        &mut self,
+        game_state: &mut GameState,
+        clock: Option<&Clock>,
+    ) -> super::controller::Command {
+        if self.draw_offered {
+            // the game goes on, so the local player declined the offer
+            self.draw_offered = false;
+            self.dont_send_last_move = true; // the opponent already has the previous move, dont send it again
+            let send_msg = serde_json::to_string(&Command::DeclineDraw).unwrap();
+            let _ = self.stream.write(send_msg.as_bytes());
+        }
         if self.undo_accepted {
             self.undo_accepted = false;
             self.dont_send_last_move = true; // the opponent already has the previous move, dont send it again
@@ -157,12 +212,56 @@ impl Controller for RemoteHuman {
                 let _ = self.stream.write(send_msg.as_bytes());
             }
         };
-        let received_message = self.receive_message();
+        let Some(received_message) = self.receive_message_in_time(clock) else {
+            // the remote player's flag fell while waiting for the command
+            return Command::Timeout;
+        };
+        if received_message == DRAW_OFFER {
+            // the game asks the local player, the reply is sent once it is known
+            self.draw_offered = true;
+            return Command::OfferDraw;
+        }
 
         let Ok(cmd) = serde_json::from_str(&received_message.as_str()) else {
-            self.handle_message(received_message, game_state);
-            return self.choose_command(game_state);
+            self.handle_message(received_message, game_state, clock);
+            return self.choose_command(game_state, clock);
         };
         return cmd;
     }
+
+    fn game_over(&mut self, game_state: &GameState, result: &GameResult) {
+        if result.termination() == Termination::Agreement {
+            if self.draw_offered {
+                // the local player accepted the opponent's offer
+                let send_msg = serde_json::to_string(&Command::AcceptDraw).unwrap();
+                let _ = self.stream.write(send_msg.as_bytes());
+            }
+            return;
+        }
+        // message for the event on the turn of the player to move that ended the game
+        let final_message = match result.termination() {
+            Termination::Resignation => Some(serde_json::to_string(&Command::Resign).unwrap()),
+            Termination::ThreefoldRepetition | Termination::FiftyMoveRule => {
+                Some(serde_json::to_string(&Command::ClaimDraw).unwrap())
+            }
+            Termination::Timeout => Some(serde_json::to_string(&Command::Timeout).unwrap()),
+            _ => None,
+        };
+        let send_msg = if game_state.player_to_move == self.color {
+            if final_message.is_some() || self.dont_send_last_move {
+                // the game was ended by the opponent, who already knows it
+                return;
+            }
+            // the local player's last movement ended the game
+            let Some(last_move) = game_state.last_move.clone() else {
+                return;
+            };
+            serde_json::to_string(&Command::Move(last_move)).unwrap()
+        } else if let Some(message) = final_message {
+            message
+        } else {
+            return;
+        };
+        let _ = self.stream.write(send_msg.as_bytes());
+    }
 }
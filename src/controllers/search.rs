@@ -0,0 +1,655 @@
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use crate::evaluation::{
+    piece_value, replace_evaluator, static_exchange_evaluation, Evaluator, FullEvaluator,
+    CHECK_MATE_VALUE,
+};
+use crate::model::clock::{Clock, TimeControl};
+use crate::model::{
+    game_state::GameState,
+    movement::Movement,
+    piece::{Color, PieceType},
+};
+use crate::rules::cmd_validator::is_in_check;
+use crate::rules::move_generator::generate_movements_for_player;
+
+use super::move_ordering::{MoveOrdering, OrderingConfig};
+use super::transposition::{Bound, Entry, TranspositionTable};
+
+pub const MAX_DEPTH: u32 = 64;
+pub const DEFAULT_HASH_SIZE_MB: usize = 16;
+const INFINITY: i32 = CHECK_MATE_VALUE + 1;
+/// Safety margin of delta pruning, for positional compensation of the material lost.
+const DELTA_MARGIN: i32 = 200;
+/// Half-width of the first aspiration window, half a pawn.
+const ASPIRATION_WINDOW: i32 = 50;
+const ASPIRATION_MIN_DEPTH: u32 = 4;
+const NULL_MOVE_MIN_DEPTH: u32 = 3;
+const LMR_MIN_DEPTH: u32 = 3;
+/// Number of movements searched at full depth before reducing the others.
+const LMR_FULL_DEPTH_MOVES: u32 = 3;
+/// Largest gain expected from a quiet movement, by remaining depth.
+const FUTILITY_MARGINS: [i32; 3] = [0, 250, 550];
+/// Number of nodes between two checks of the time limit.
+const NODES_BETWEEN_TIME_CHECKS: u64 = 256;
+
+/// When the iterative deepening must stop. The search always completes depth 1,
+/// so that a movement is found even with a tiny budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: u32,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth,
+            time: None,
+            nodes: None,
+        }
+    }
+}
+
+/// Search techniques that can be switched off, to compare their effect on the playing strength.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub ordering: OrderingConfig,
+    /// Zero-window searches for the movements after the first one.
+    pub principal_variation_search: bool,
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    pub futility_pruning: bool,
+    /// Search one ply deeper when the king is in check.
+    pub check_extensions: bool,
+    /// Start each iteration with a narrow window around the previous score.
+    pub aspiration_windows: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            ordering: OrderingConfig::default(),
+            principal_variation_search: true,
+            null_move_pruning: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            check_extensions: true,
+            aspiration_windows: true,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Plain alpha-beta search.
+    pub fn disabled() -> Self {
+        Self {
+            ordering: OrderingConfig::default(),
+            principal_variation_search: false,
+            null_move_pruning: false,
+            late_move_reductions: false,
+            futility_pruning: false,
+            check_extensions: false,
+            aspiration_windows: false,
+        }
+    }
+}
+
+/// Outcome of the last completed iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<Movement>,
+    /// Score for the player to move.
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Iterative deepening negamax search with alpha-beta pruning.
+/// The transposition table is kept between searches.
+#[derive(Clone)]
+pub struct Search {
+    limits: SearchLimits,
+    config: SearchConfig,
+    evaluator: Arc<dyn Evaluator>,
+    table: TranspositionTable,
+    ordering: MoveOrdering,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    /// Limits are only checked after the first iteration is completed.
+    can_stop: bool,
+}
+
+impl Search {
+    pub fn new(limits: SearchLimits) -> Self {
+        Self {
+            limits,
+            config: SearchConfig::default(),
+            evaluator: Arc::new(FullEvaluator),
+            table: TranspositionTable::new(DEFAULT_HASH_SIZE_MB),
+            ordering: MoveOrdering::new(OrderingConfig::default()),
+            start: Instant::now(),
+            nodes: 0,
+            stopped: false,
+            can_stop: false,
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.limits = limits;
+    }
+
+    /// Replaces the transposition table by an empty one of the given size.
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.table = TranspositionTable::new(size_mb);
+    }
+
+    pub fn set_config(&mut self, config: SearchConfig) {
+        self.config = config;
+        self.ordering.config = config.ordering;
+    }
+
+    pub fn evaluator(&self) -> &dyn Evaluator {
+        self.evaluator.as_ref()
+    }
+
+    pub fn set_evaluator(&mut self, evaluator: impl Evaluator + 'static) {
+        replace_evaluator(&mut self.evaluator, evaluator, || self.table.clear());
+    }
+
+    pub fn run(&mut self, game_state: &GameState) -> SearchResult {
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.stopped = false;
+        self.can_stop = false;
+        self.ordering.new_search();
+
+        // the movements are made and taken back on a single copy of the game state
+        let game_state = &mut game_state.clone();
+        let player = game_state.player_to_move;
+        let mut root_movements = generate_movements_for_player(game_state, player);
+        // vary the choice between movements with the same score
+        root_movements.shuffle(&mut thread_rng());
+        self.ordering
+            .sort(&mut root_movements, game_state, &None, 0);
+
+        let mut result = SearchResult {
+            best_move: root_movements.first().cloned(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+        };
+        if root_movements.len() <= 1 {
+            return result;
+        }
+
+        for depth in 1..=self.limits.depth.max(1) {
+            let Some((best_move, score)) =
+                self.search_with_aspiration(game_state, &root_movements, depth, result.score)
+            else {
+                // use the best movement of the last completed iteration
+                break;
+            };
+            // search the best movement first in the next iteration
+            let index = root_movements.iter().position(|m| *m == best_move).unwrap();
+            root_movements[..=index].rotate_right(1);
+            result = SearchResult {
+                best_move: Some(best_move),
+                score,
+                depth,
+                nodes: self.nodes,
+            };
+            self.can_stop = true;
+            if score.abs() > CHECK_MATE_VALUE - MAX_DEPTH as i32
+                || !self.has_time_for_next_iteration()
+            {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result
+    }
+
+    /// Searches a narrow window around the score of the previous iteration,
+    /// which is widened when the score falls outside of it.
+    fn search_with_aspiration(
+        &mut self,
+        game_state: &mut GameState,
+        movements: &[Movement],
+        depth: u32,
+        previous_score: i32,
+    ) -> Option<(Movement, i32)> {
+        if !self.config.aspiration_windows || depth < ASPIRATION_MIN_DEPTH {
+            return self.search_root(game_state, movements, depth, -INFINITY, INFINITY);
+        }
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = (previous_score - delta).max(-INFINITY);
+        let mut beta = (previous_score + delta).min(INFINITY);
+        loop {
+            let (best_move, score) = self.search_root(game_state, movements, depth, alpha, beta)?;
+            delta *= 2;
+            if score <= alpha && alpha > -INFINITY {
+                alpha = (alpha - delta).max(-INFINITY);
+            } else if score >= beta && beta < INFINITY {
+                beta = (beta + delta).min(INFINITY);
+            } else {
+                return Some((best_move, score));
+            }
+        }
+    }
+
+    /// Best movement and its score, or None if the search was stopped before completing the depth.
+    fn search_root(
+        &mut self,
+        game_state: &mut GameState,
+        movements: &[Movement],
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<(Movement, i32)> {
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for (i, movement) in movements.iter().enumerate() {
+            let undo = game_state.make_move(movement.clone());
+            self.nodes += 1;
+            let score = if i > 0 && self.config.principal_variation_search {
+                let score = -self.negamax(game_state, depth - 1, 1, -alpha - 1, -alpha);
+                if score > alpha && score < beta {
+                    -self.negamax(game_state, depth - 1, 1, -beta, -alpha)
+                } else {
+                    score
+                }
+            } else {
+                -self.negamax(game_state, depth - 1, 1, -beta, -alpha)
+            };
+            game_state.unmake_move(undo);
+            if self.stopped {
+                return None;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(movement.clone());
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_move.map(|movement| (movement, best_score))
+    }
+
+    fn negamax(
+        &mut self,
+        game_state: &mut GameState,
+        mut depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        // a repeated position is scored as a draw, since the players can keep repeating it
+        if game_state.repetition_count() >= 2 || game_state.halfmove_clock >= 100 {
+            return 0;
+        }
+        let player = game_state.player_to_move;
+        if ply >= MAX_DEPTH {
+            return self.evaluator.evaluate(game_state, player);
+        }
+        let in_check =
+            (depth > 0 || self.config.check_extensions) && is_in_check(game_state, player);
+        if in_check && self.config.check_extensions {
+            // don't stop the search while the king is attacked
+            depth += 1;
+        }
+        if depth == 0 {
+            return self.quiescence(game_state, alpha, beta);
+        }
+
+        let key = game_state.zobrist_hash();
+        let mut hash_move = None;
+        if let Some(entry) = self.table.probe(key) {
+            let score = score_from_table(entry.score, ply);
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+            hash_move = entry.best_move.clone();
+        }
+
+        let is_zero_window = beta - alpha == 1;
+        let static_eval = self.evaluator.evaluate(game_state, player);
+
+        // null-move pruning: if passing the turn still fails high, a real movement would too.
+        // Not used after another null move nor in endings without pieces, where zugzwang is common.
+        if self.config.null_move_pruning
+            && is_zero_window
+            && !in_check
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && static_eval >= beta
+            && beta.abs() < CHECK_MATE_VALUE - MAX_DEPTH as i32
+            && game_state.last_move.is_some()
+            && has_pieces(game_state, player)
+        {
+            let reduction = if depth > 6 { 3 } else { 2 };
+            let null_move = make_null_move(game_state);
+            let score = -self.negamax(game_state, depth - 1 - reduction, ply + 1, -beta, -beta + 1);
+            unmake_null_move(game_state, null_move);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+        }
+
+        // futility pruning: near the leaves, quiet movements can't raise a hopeless score
+        let is_futile = self.config.futility_pruning
+            && !in_check
+            && (depth as usize) < FUTILITY_MARGINS.len()
+            && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+
+        let mut movements = generate_movements_for_player(game_state, player);
+        self.ordering
+            .sort(&mut movements, game_state, &hash_move, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut legal_movements = 0;
+        for movement in movements {
+            legal_movements += 1;
+            let is_quiet = !is_capture_or_promotion(game_state, &movement);
+            if is_futile && is_quiet && legal_movements > 1 {
+                continue;
+            }
+            let undo = game_state.make_move(movement.clone());
+            self.nodes += 1;
+
+            // late move reductions: movements ordered last are searched less deep, unless they
+            // turn out to be better than expected
+            let reduction = if self.config.late_move_reductions
+                && depth >= LMR_MIN_DEPTH
+                && legal_movements > LMR_FULL_DEPTH_MOVES
+                && is_quiet
+                && !in_check
+                && !is_in_check(game_state, player.get_opponent_color())
+            {
+                if legal_movements > 2 * LMR_FULL_DEPTH_MOVES && depth >= 6 {
+                    2
+                } else {
+                    1
+                }
+            } else {
+                0
+            };
+
+            let score = if legal_movements == 1 {
+                -self.negamax(game_state, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                // principal variation search: the first movement is expected to be the best,
+                // so the others are only tested with a zero window
+                let (low, high) = if self.config.principal_variation_search {
+                    (-alpha - 1, -alpha)
+                } else {
+                    (-beta, -alpha)
+                };
+                let mut score =
+                    -self.negamax(game_state, depth - 1 - reduction, ply + 1, low, high);
+                if reduction > 0 && score > alpha {
+                    score = -self.negamax(game_state, depth - 1, ply + 1, low, high);
+                }
+                if self.config.principal_variation_search && score > alpha && score < beta {
+                    score = -self.negamax(game_state, depth - 1, ply + 1, -beta, -alpha);
+                }
+                score
+            };
+            game_state.unmake_move(undo);
+            if self.stopped {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(movement.clone());
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                self.ordering.update(&movement, game_state, depth, ply);
+                break;
+            }
+        }
+
+        if legal_movements == 0 {
+            // no legal movements: checkmate or stalemate
+            return if in_check {
+                // prefer the fastest checkmate
+                -CHECK_MATE_VALUE + ply as i32
+            } else {
+                0
+            };
+        }
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(Entry {
+            key,
+            depth,
+            bound,
+            score: score_to_table(best_score, ply),
+            best_move,
+        });
+        best_score
+    }
+
+    /// Searches captures and promotions until the position is quiet, so that the evaluation
+    /// doesn't stop in the middle of an exchange (horizon effect).
+    fn quiescence(&mut self, game_state: &mut GameState, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        let player = game_state.player_to_move;
+        // the player can usually avoid capturing, so the static evaluation is a lower bound
+        let stand_pat = self.evaluator.evaluate(game_state, player);
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut movements: Vec<(i32, Movement)> = generate_movements_for_player(game_state, player)
+            .into_iter()
+            .filter_map(|movement| {
+                let gain = material_gain(game_state, &movement)?;
+                Some((gain, movement))
+            })
+            .collect();
+        // most valuable captures first
+        movements.sort_by_key(|(gain, _)| -gain);
+
+        let mut best_score = stand_pat;
+        for (gain, movement) in movements {
+            // delta pruning: even winning the piece for free would not raise alpha
+            if stand_pat + gain + DELTA_MARGIN <= alpha {
+                break;
+            }
+            // captures that lose material in the exchange
+            if !is_promotion(game_state, &movement)
+                && static_exchange_evaluation(game_state, &movement) < 0
+            {
+                continue;
+            }
+            let undo = game_state.make_move(movement);
+            self.nodes += 1;
+            let score = -self.quiescence(game_state, -beta, -alpha);
+            game_state.unmake_move(undo);
+            if self.stopped {
+                return 0;
+            }
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if !self.can_stop {
+            return false;
+        }
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes >= nodes {
+                self.stopped = true;
+            }
+        }
+        if let Some(time) = self.limits.time {
+            if self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS) && self.start.elapsed() >= time
+            {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    /// The next iteration takes several times longer than the previous ones,
+    /// so it is not started if it would most likely be interrupted.
+    fn has_time_for_next_iteration(&self) -> bool {
+        match self.limits.time {
+            Some(time) => self.start.elapsed() < time / 2,
+            None => true,
+        }
+    }
+}
+
+fn is_capture_or_promotion(game_state: &GameState, movement: &Movement) -> bool {
+    material_gain(game_state, movement).is_some()
+}
+
+/// Whether the player has other pieces than the king and pawns.
+fn has_pieces(game_state: &GameState, player: Color) -> bool {
+    game_state.board.iter().flatten().flatten().any(|piece| {
+        piece.color == player && !matches!(piece.piece_type, PieceType::King | PieceType::Pawn)
+    })
+}
+
+/// State changed by passing the turn, to restore it afterwards.
+struct NullMove {
+    en_passant_square: Option<[usize; 2]>,
+    last_move: Option<Movement>,
+    position_history: Vec<u64>,
+}
+
+/// The player passes the turn to the opponent.
+fn make_null_move(game_state: &mut GameState) -> NullMove {
+    game_state.player_to_move = game_state.player_to_move.get_opponent_color();
+    NullMove {
+        en_passant_square: game_state.en_passant_square.take(),
+        last_move: game_state.last_move.take(),
+        // passing is not a real movement, so it can't cause repetitions
+        position_history: mem::take(&mut game_state.position_history),
+    }
+}
+
+fn unmake_null_move(game_state: &mut GameState, null_move: NullMove) {
+    game_state.player_to_move = game_state.player_to_move.get_opponent_color();
+    game_state.en_passant_square = null_move.en_passant_square;
+    game_state.last_move = null_move.last_move;
+    game_state.position_history = null_move.position_history;
+}
+
+/// Material won by a capture or a promotion, or None for quiet movements.
+fn material_gain(game_state: &GameState, movement: &Movement) -> Option<i32> {
+    let Movement::Normal {
+        from,
+        to,
+        promotion,
+    } = movement
+    else {
+        return None;
+    };
+    let piece = game_state.board[from[0]][from[1]]?;
+    let captured = match game_state.board[to[0]][to[1]] {
+        Some(captured) => Some(piece_value(captured.piece_type)),
+        None if piece.piece_type == PieceType::Pawn
+            && game_state.en_passant_square == Some(*to) =>
+        {
+            Some(piece_value(PieceType::Pawn))
+        }
+        None => None,
+    };
+    if is_promotion(game_state, movement) {
+        let promotion = promotion.unwrap_or(PieceType::Queen);
+        return Some(captured.unwrap_or(0) + piece_value(promotion) - piece_value(PieceType::Pawn));
+    }
+    captured
+}
+
+fn is_promotion(game_state: &GameState, movement: &Movement) -> bool {
+    let Movement::Normal { from, to, .. } = movement else {
+        return false;
+    };
+    matches!(
+        game_state.board[from[0]][from[1]],
+        Some(piece) if piece.piece_type == PieceType::Pawn && (to[0] == 0 || to[0] == 7)
+    )
+}
+
+/// Checkmate scores are stored relative to the position instead of the root,
+/// so that they stay correct when the position is reached at another ply.
+fn score_to_table(score: i32, ply: u32) -> i32 {
+    if score > CHECK_MATE_VALUE - MAX_DEPTH as i32 {
+        score + ply as i32
+    } else if score < -CHECK_MATE_VALUE + MAX_DEPTH as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: u32) -> i32 {
+    if score > CHECK_MATE_VALUE - MAX_DEPTH as i32 {
+        score - ply as i32
+    } else if score < -CHECK_MATE_VALUE + MAX_DEPTH as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Time to spend on the next movement of the player.
+/// Spreads the remaining time over the expected number of movements and uses most of the bonus.
+pub fn time_budget(clock: &Clock, player: Color) -> Duration {
+    let remaining = clock.get_remaining(player);
+    let (moves_to_go, bonus) = match clock.time_control {
+        TimeControl::SuddenDeath { .. } => (30, Duration::ZERO),
+        TimeControl::Increment { increment, .. } => (30, increment),
+        TimeControl::Delay { delay, .. } => (30, delay),
+        TimeControl::MovesPerPeriod { moves, .. } => (
+            moves - clock.moves_in_period[player as usize],
+            Duration::ZERO,
+        ),
+    };
+    let budget = remaining / moves_to_go.max(1) + bonus * 3 / 4;
+    // keep a safety margin to not lose on time
+    budget.min(remaining / 2)
+}
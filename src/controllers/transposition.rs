@@ -0,0 +1,81 @@
+use std::mem::size_of;
+
+use crate::model::movement::Movement;
+
+/// How the stored score relates to the real score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search failed high: the real score is at least the stored score.
+    Lower,
+    /// The search failed low: the real score is at most the stored score.
+    Upper,
+}
+
+/// Result of a previous search of a position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Movement>,
+}
+
+/// Fixed-size hash table of searched positions, indexed by their Zobrist hash.
+/// Positions reached by different movement orders are only searched once.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// Table using up to the given number of megabytes. A size of 0 disables the table.
+    pub fn new(size_mb: usize) -> Self {
+        let number_of_entries = size_mb * 1024 * 1024 / size_of::<Option<Entry>>();
+        Self {
+            entries: vec![None; number_of_entries],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    /// Entry of the position with the given hash, if it is in the table.
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        match &self.entries[self.index(key)] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// Stores an entry, unless it would replace a deeper search of the same position.
+    pub fn store(&mut self, entry: Entry) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        match slot {
+            Some(old_entry) if old_entry.key == entry.key && old_entry.depth > entry.depth => {}
+            _ => *slot = Some(entry),
+        }
+    }
+}
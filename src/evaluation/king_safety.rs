@@ -0,0 +1,55 @@
+use crate::model::{
+    board::Board,
+    piece::{Color, Piece, PieceType},
+};
+
+use super::pawn_structure::is_pawn;
+use super::{forward, sub, Score};
+
+/// Bonus of a pawn in front of the king, by distance to the king.
+const PAWN_SHIELD: [i32; 2] = [12, 6];
+const MISSING_SHIELD_PAWN: i32 = -15;
+const OPEN_FILE_NEAR_KING: i32 = -25;
+const SEMI_OPEN_FILE_NEAR_KING: i32 = -12;
+
+/// Pawn shield and open files next to the kings, from White's point of view.
+/// Only relevant in the middlegame, while the opponent has enough pieces to attack.
+pub fn evaluate_king_safety(board: &Board) -> Score {
+    sub(
+        [evaluate_king_shelter(board, Color::White), 0],
+        [evaluate_king_shelter(board, Color::Black), 0],
+    )
+}
+
+fn evaluate_king_shelter(board: &Board, color: Color) -> i32 {
+    let king = Some(Piece {
+        piece_type: PieceType::King,
+        color,
+    });
+    let Some((king_row, king_col)) = (0..8)
+        .flat_map(|row| (0..8).map(move |col| (row, col)))
+        .find(|&(row, col)| board[row as usize][col as usize] == king)
+    else {
+        return 0;
+    };
+    let opponent = color.get_opponent_color();
+    let mut score = 0;
+    for col in (king_col - 1..=king_col + 1).filter(|c| (0..8).contains(c)) {
+        let shield_pawn = (1..=2)
+            .find(|distance| is_pawn(board, king_row + distance * forward(color), col, color));
+        score += match shield_pawn {
+            Some(distance) => PAWN_SHIELD[distance as usize - 1],
+            None => MISSING_SHIELD_PAWN,
+        };
+        let has_own_pawn = (0..8).any(|row| is_pawn(board, row, col, color));
+        let has_opponent_pawn = (0..8).any(|row| is_pawn(board, row, col, opponent));
+        if !has_own_pawn {
+            score += if has_opponent_pawn {
+                SEMI_OPEN_FILE_NEAR_KING
+            } else {
+                OPEN_FILE_NEAR_KING
+            };
+        }
+    }
+    score
+}
@@ -0,0 +1,295 @@
+use std::sync::Arc;
+
+use crate::model::bitboard::{position_bit, positions, Bitboard};
+use crate::model::movement::Movement;
+use crate::model::{game_state::GameState, piece::Color, piece::Piece, piece::PieceType};
+use crate::rules::game_over::{is_draw, is_in_check_mate};
+use crate::view::{AsciiDisplay, GameDisplay};
+
+pub mod king_safety;
+pub mod pawn_structure;
+pub mod piece_activity;
+mod piece_square_tables;
+
+use king_safety::evaluate_king_safety;
+use pawn_structure::evaluate_pawn_structure;
+use piece_activity::evaluate_piece_activity;
+use piece_square_tables::{ENDGAME_TABLES, MIDDLEGAME_TABLES};
+
+/// Values in centipawns.
+pub const KING_VALUE: i32 = 10000;
+const QUEEN_VALUE: i32 = 1000;
+const KNIGHT_VALUE: i32 = 300;
+const BISHOP_VALUE: i32 = 325;
+const ROOK_VALUE: i32 = 500;
+const PAWN_VALUE: i32 = 100;
+pub const CHECK_MATE_VALUE: i32 = 20000;
+
+/// Scores positions for the searches. Implement it to plug a custom evaluation into the bots.
+pub trait Evaluator: Send + Sync {
+    /// Score of the position for the player, in centipawns.
+    fn evaluate(&self, game_state: &GameState, player_color: Color) -> i32;
+}
+
+/// Functions and closures with the signature of `evaluate` are evaluators.
+impl<F> Evaluator for F
+where
+    F: Fn(&GameState, Color) -> i32 + Send + Sync,
+{
+    fn evaluate(&self, game_state: &GameState, player_color: Color) -> i32 {
+        self(game_state, player_color)
+    }
+}
+
+/// Replace the evaluator of a search. The scores that the search keeps from the previous
+/// evaluator are not comparable with the new ones, so `clear_scores` drops them.
+pub(crate) fn replace_evaluator(
+    current: &mut Arc<dyn Evaluator>,
+    evaluator: impl Evaluator + 'static,
+    clear_scores: impl FnOnce(),
+) {
+    *current = Arc::new(evaluator);
+    clear_scores();
+}
+
+/// Only counts the material, see `evaluate_material`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterialEvaluator;
+impl Evaluator for MaterialEvaluator {
+    fn evaluate(&self, game_state: &GameState, player_color: Color) -> i32 {
+        evaluate_material(game_state, player_color)
+    }
+}
+
+/// Material and piece-square tables, see `evaluate_positions`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PositionalEvaluator;
+impl Evaluator for PositionalEvaluator {
+    fn evaluate(&self, game_state: &GameState, player_color: Color) -> i32 {
+        evaluate_positions(game_state, player_color)
+    }
+}
+
+/// All the evaluation terms, see `evaluate_full`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullEvaluator;
+impl Evaluator for FullEvaluator {
+    fn evaluate(&self, game_state: &GameState, player_color: Color) -> i32 {
+        evaluate_full(game_state, player_color)
+    }
+}
+
+/// Middlegame and endgame values of an evaluation term.
+pub type Score = [i32; 2];
+
+fn add(a: Score, b: Score) -> Score {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: Score, b: Score) -> Score {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+/// Direction in which the pawns of the color advance.
+fn forward(color: Color) -> i32 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
+
+/// Rank from the color's point of view, from 0 to 7.
+fn relative_rank(row: usize, color: Color) -> usize {
+    match color {
+        Color::White => row,
+        Color::Black => 7 - row,
+    }
+}
+
+pub fn piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::King => KING_VALUE,
+        PieceType::Queen => QUEEN_VALUE,
+        PieceType::Rook => ROOK_VALUE,
+        PieceType::Bishop => BISHOP_VALUE,
+        PieceType::Knight => KNIGHT_VALUE,
+        PieceType::Pawn => PAWN_VALUE,
+    }
+}
+
+/// Sums the value of all pieces of the player, minus the opponent's pieces.
+pub fn evaluate_material(game_state: &GameState, player_color: Color) -> i32 {
+    let mut score = 0;
+    for x in 0..8 {
+        for y in 0..8 {
+            if let Some(piece) = game_state.board[x][y] {
+                let value = piece_value(piece.piece_type);
+                if piece.color == player_color {
+                    score += value;
+                } else {
+                    score -= value;
+                }
+            }
+        }
+    }
+    return score;
+}
+
+/// Contribution of the pieces to the game phase, from the endgame (0) to the middlegame (24).
+fn phase_weight(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Queen => 4,
+        PieceType::Rook => 2,
+        PieceType::Bishop | PieceType::Knight => 1,
+        PieceType::King | PieceType::Pawn => 0,
+    }
+}
+
+const MIDDLEGAME_PHASE: i32 = 24;
+
+/// Material and piece-square table scores from White's point of view, and the game phase.
+fn evaluate_piece_squares(game_state: &GameState) -> (i32, Score, i32) {
+    let mut material = 0;
+    let mut score = [0, 0];
+    let mut phase = 0;
+    for x in 0..8 {
+        for y in 0..8 {
+            if let Some(piece) = game_state.board[x][y] {
+                // the tables are written from White's point of view, with the 8th rank first
+                let square = match piece.color {
+                    Color::White => (7 - x) * 8 + y,
+                    Color::Black => x * 8 + y,
+                };
+                let table = piece.piece_type as usize;
+                let sign = if piece.color == Color::White { 1 } else { -1 };
+                material += sign * piece_value(piece.piece_type);
+                score = add(
+                    score,
+                    [
+                        sign * MIDDLEGAME_TABLES[table][square],
+                        sign * ENDGAME_TABLES[table][square],
+                    ],
+                );
+                phase += phase_weight(piece.piece_type);
+            }
+        }
+    }
+    // early promotions can give more material than the initial position
+    (material, score, phase.min(MIDDLEGAME_PHASE))
+}
+
+/// Blends the middlegame and endgame values according to the material left on the board.
+fn taper(score: Score, phase: i32) -> i32 {
+    (score[0] * phase + score[1] * (MIDDLEGAME_PHASE - phase)) / MIDDLEGAME_PHASE
+}
+
+fn from_player_point_of_view(score: i32, player_color: Color) -> i32 {
+    match player_color {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// Material plus piece-square tables, blended between the middlegame and the endgame tables
+/// according to the material left on the board (tapered evaluation).
+pub fn evaluate_positions(game_state: &GameState, player_color: Color) -> i32 {
+    let (material, piece_squares, phase) = evaluate_piece_squares(game_state);
+    from_player_point_of_view(material + taper(piece_squares, phase), player_color)
+}
+
+/// Positional evaluation plus pawn structure, king safety and piece activity.
+pub fn evaluate_full(game_state: &GameState, player_color: Color) -> i32 {
+    let (material, piece_squares, phase) = evaluate_piece_squares(game_state);
+    let score = add(
+        add(piece_squares, evaluate_pawn_structure(game_state)),
+        add(
+            evaluate_king_safety(&game_state.board),
+            evaluate_piece_activity(game_state),
+        ),
+    );
+    from_player_point_of_view(material + taper(score, phase), player_color)
+}
+
+pub fn evaluate_game_over(game_state: &GameState, player_color: Color) -> i32 {
+    if is_draw(game_state) {
+        0
+    } else if is_in_check_mate(game_state, player_color) {
+        -CHECK_MATE_VALUE
+    } else if is_in_check_mate(game_state, player_color.get_opponent_color()) {
+        CHECK_MATE_VALUE
+    } else {
+        println!("evaluate_game_over called for unfinished game");
+        AsciiDisplay.display_game(game_state);
+        panic!();
+    }
+}
+
+/// Static exchange evaluation: material won by the player making the capture,
+/// assuming both players keep recapturing on the destination square with their least valuable piece
+/// while it is profitable. Movements that capture nothing are evaluated as 0.
+pub fn static_exchange_evaluation(game_state: &GameState, movement: &Movement) -> i32 {
+    let Movement::Normal { from, to, .. } = *movement else {
+        return 0;
+    };
+    let board = &game_state.board;
+    let Some(piece) = board[from[0]][from[1]] else {
+        return 0;
+    };
+    let mut occupied = board.occupied();
+    let captured =
+        if piece.piece_type == PieceType::Pawn && game_state.en_passant_square == Some(to) {
+            // the pawn taken en passant is behind the destination square
+            occupied &= !position_bit([from[0], to[1]]);
+            Some(PieceType::Pawn)
+        } else {
+            board[to[0]][to[1]].map(|piece| piece.piece_type)
+        };
+    let Some(captured) = captured else {
+        return 0;
+    };
+    exchange(game_state, occupied, from, to, piece_value(captured))
+}
+
+/// Value of capturing a piece on the square, followed by the best sequence of recaptures of the opponent.
+/// The pieces that already captured are no longer among the occupied squares.
+fn exchange(
+    game_state: &GameState,
+    occupied: Bitboard,
+    from: [usize; 2],
+    to: [usize; 2],
+    captured: i32,
+) -> i32 {
+    let piece = game_state.board[from[0]][from[1]].unwrap();
+    let occupied = occupied & !position_bit(from);
+    let opponent = piece.color.get_opponent_color();
+    let recapture = match least_valuable_attacker(game_state, occupied, to, opponent) {
+        // the opponent only recaptures if it doesn't lose material
+        Some(attacker) => {
+            exchange(game_state, occupied, attacker, to, piece_value(piece.piece_type)).max(0)
+        }
+        None => 0,
+    };
+    captured - recapture
+}
+
+/// Position of the cheapest piece of the color that attacks the square.
+/// Pieces behind an attacker are found once the attacker has left the occupied squares (x-rays).
+fn least_valuable_attacker(
+    game_state: &GameState,
+    occupied: Bitboard,
+    square: [usize; 2],
+    color: Color,
+) -> Option<[usize; 2]> {
+    let attackers = game_state.attackers_with_occupancy(square, color, occupied);
+    let board = &game_state.board;
+    [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ]
+    .into_iter()
+    .find_map(|piece_type| positions(attackers & board.pieces(Piece { piece_type, color })).next())
+}
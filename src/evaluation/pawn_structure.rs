@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+
+use crate::model::{
+    board::Board,
+    game_state::GameState,
+    piece::{Color, Piece, PieceType},
+};
+
+use super::{add, forward, relative_rank, sub, Score};
+
+const DOUBLED_PAWN: Score = [-10, -20];
+const ISOLATED_PAWN: Score = [-15, -10];
+const BACKWARD_PAWN: Score = [-10, -8];
+/// Bonus of passed pawns by rank, from the player's point of view.
+const PASSED_PAWN: [Score; 8] = [
+    [0, 0],
+    [5, 10],
+    [10, 20],
+    [15, 35],
+    [25, 60],
+    [40, 100],
+    [60, 150],
+    [0, 0],
+];
+const PAWN_HASH_SIZE: usize = 1 << 14;
+
+thread_local! {
+    /// Pawn structure scores by pawn hash. Pawns move rarely, so most positions of a search
+    /// share their pawn structure with positions already evaluated.
+    static PAWN_HASH: RefCell<Vec<Option<(u64, Score)>>> = RefCell::new(vec![None; PAWN_HASH_SIZE]);
+}
+
+pub fn is_pawn(board: &Board, row: i32, col: i32, color: Color) -> bool {
+    (0..8).contains(&row)
+        && (0..8).contains(&col)
+        && board[row as usize][col as usize]
+            == Some(Piece {
+                piece_type: PieceType::Pawn,
+                color,
+            })
+}
+
+/// Whether a pawn of `pawn_color` on the file is ahead of the row, from the color's point of view.
+fn has_pawn_ahead(board: &Board, row: i32, col: i32, color: Color, pawn_color: Color) -> bool {
+    (0..8).any(|r| (r - row) * forward(color) > 0 && is_pawn(board, r, col, pawn_color))
+}
+
+/// Doubled, isolated, backward and passed pawns, from White's point of view.
+pub fn evaluate_pawn_structure(game_state: &GameState) -> Score {
+    let key = game_state.pawn_hash();
+    let index = (key % PAWN_HASH_SIZE as u64) as usize;
+    PAWN_HASH.with(|table| {
+        let mut table = table.borrow_mut();
+        match table[index] {
+            Some((entry_key, score)) if entry_key == key => score,
+            _ => {
+                let score = sub(
+                    evaluate_pawns(&game_state.board, Color::White),
+                    evaluate_pawns(&game_state.board, Color::Black),
+                );
+                table[index] = Some((key, score));
+                score
+            }
+        }
+    })
+}
+
+fn evaluate_pawns(board: &Board, color: Color) -> Score {
+    let opponent = color.get_opponent_color();
+    let mut score = [0, 0];
+    for col in 0..8 {
+        let rows: Vec<i32> = (0..8)
+            .filter(|&row| is_pawn(board, row, col, color))
+            .collect();
+        for _ in 1..rows.len() {
+            score = add(score, DOUBLED_PAWN);
+        }
+        for &row in &rows {
+            let neighbour_rows: Vec<i32> = [col - 1, col + 1]
+                .into_iter()
+                .flat_map(|c| (0..8).filter(move |&r| is_pawn(board, r, c, color)))
+                .collect();
+            // no opponent pawn can block or capture it on its way
+            let is_passed =
+                (col - 1..=col + 1).all(|c| !has_pawn_ahead(board, row, c, color, opponent));
+            if neighbour_rows.is_empty() {
+                score = add(score, ISOLATED_PAWN);
+            } else if !is_passed {
+                // the neighbours are too advanced to protect the pawn, and it can't advance safely
+                let is_behind_neighbours = neighbour_rows
+                    .iter()
+                    .all(|r| (r - row) * forward(color) > 0);
+                let stop_row = row + forward(color);
+                let stop_square_is_attacked =
+                    is_pawn(board, stop_row + forward(color), col - 1, opponent)
+                        || is_pawn(board, stop_row + forward(color), col + 1, opponent);
+                if is_behind_neighbours && stop_square_is_attacked {
+                    score = add(score, BACKWARD_PAWN);
+                }
+            }
+            if is_passed && !has_pawn_ahead(board, row, col, color, color) {
+                score = add(score, PASSED_PAWN[relative_rank(row as usize, color)]);
+            }
+        }
+    }
+    score
+}
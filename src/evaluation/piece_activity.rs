@@ -0,0 +1,103 @@
+use crate::model::{
+    attack_map::AttackMap,
+    board::Board,
+    game_state::GameState,
+    piece::{Color, PieceType},
+};
+
+use super::pawn_structure::is_pawn;
+use super::{add, forward, relative_rank, sub, Score};
+
+/// Bonus for each square attacked by the piece.
+const KNIGHT_MOBILITY: Score = [4, 4];
+const BISHOP_MOBILITY: Score = [5, 5];
+const ROOK_MOBILITY: Score = [2, 4];
+const QUEEN_MOBILITY: Score = [1, 2];
+const BISHOP_PAIR: Score = [30, 50];
+const ROOK_ON_OPEN_FILE: Score = [25, 10];
+const ROOK_ON_SEMI_OPEN_FILE: Score = [12, 5];
+const KNIGHT_OUTPOST: Score = [25, 15];
+const BISHOP_OUTPOST: Score = [12, 6];
+
+/// Mobility, bishop pair, rooks on open files and outposts, from White's point of view.
+pub fn evaluate_piece_activity(game_state: &GameState) -> Score {
+    let attack_map = game_state.attack_map();
+    sub(
+        evaluate_pieces(game_state, &attack_map, Color::White),
+        evaluate_pieces(game_state, &attack_map, Color::Black),
+    )
+}
+
+fn scale(score: Score, factor: i32) -> Score {
+    [score[0] * factor, score[1] * factor]
+}
+
+fn evaluate_pieces(game_state: &GameState, attack_map: &AttackMap, color: Color) -> Score {
+    let board = &game_state.board;
+    let opponent = color.get_opponent_color();
+    let mut score = [0, 0];
+    let mut bishops = 0;
+    for row in 0..8 {
+        for col in 0..8 {
+            let Some(piece) = board[row as usize][col as usize] else {
+                continue;
+            };
+            if piece.color != color {
+                continue;
+            }
+            // squares attacked by the piece that are empty or occupied by the opponent
+            let attacks = attack_map.attacks_from([row as usize, col as usize]);
+            let mobility = (attacks & !board.color(color)).count_ones() as i32;
+            match piece.piece_type {
+                PieceType::Knight => {
+                    score = add(score, scale(KNIGHT_MOBILITY, mobility));
+                    if is_outpost(board, row, col, color) {
+                        score = add(score, KNIGHT_OUTPOST);
+                    }
+                }
+                PieceType::Bishop => {
+                    bishops += 1;
+                    score = add(score, scale(BISHOP_MOBILITY, mobility));
+                    if is_outpost(board, row, col, color) {
+                        score = add(score, BISHOP_OUTPOST);
+                    }
+                }
+                PieceType::Rook => {
+                    score = add(score, scale(ROOK_MOBILITY, mobility));
+                    let has_own_pawn = (0..8).any(|r| is_pawn(board, r, col, color));
+                    let has_opponent_pawn = (0..8).any(|r| is_pawn(board, r, col, opponent));
+                    if !has_own_pawn {
+                        score = add(
+                            score,
+                            if has_opponent_pawn {
+                                ROOK_ON_SEMI_OPEN_FILE
+                            } else {
+                                ROOK_ON_OPEN_FILE
+                            },
+                        );
+                    }
+                }
+                PieceType::Queen => {
+                    score = add(score, scale(QUEEN_MOBILITY, mobility));
+                }
+                PieceType::King | PieceType::Pawn => {}
+            }
+        }
+    }
+    if bishops >= 2 {
+        score = add(score, BISHOP_PAIR);
+    }
+    score
+}
+
+/// Square in the opponent's half, protected by a pawn, that opponent pawns can never attack.
+fn is_outpost(board: &Board, row: i32, col: i32, color: Color) -> bool {
+    let opponent = color.get_opponent_color();
+    let rank = relative_rank(row as usize, color);
+    let is_protected = is_pawn(board, row - forward(color), col - 1, color)
+        || is_pawn(board, row - forward(color), col + 1, color);
+    let can_be_attacked = [col - 1, col + 1]
+        .into_iter()
+        .any(|c| (0..8).any(|r| (r - row) * forward(color) > 0 && is_pawn(board, r, c, opponent)));
+    (3..=5).contains(&rank) && is_protected && !can_be_attacked
+}
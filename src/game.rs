@@ -1,7 +1,15 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
 use crate::controllers::controller::{Command, Controller};
-use crate::model::{game_state::write_game_state_to_json, game_state::GameState, piece::Color};
+use crate::model::{clock::Clock, game_state::GameState, piece::Color};
+use crate::notation::pgn::write_game_to_pgn;
 use crate::rules::cmd_validator::is_valid_cmd;
-use crate::rules::game_over::{is_draw, is_in_check_mate};
+use crate::rules::game_over::{get_claimable_draw, get_game_result, get_timeout_result};
 use crate::view::GameDisplay;
 
 pub struct Game {
@@ -9,12 +17,79 @@ pub struct Game {
     pub game_display: Box<dyn GameDisplay>,
     pub controllers: [Box<dyn Controller>; 2],
     pub history: Vec<GameState>,
+    /// Remaining time of the players, if the game is played with a clock.
+    pub clock: Option<Clock>,
+    /// Clock before each movement of the game, to give the time back when it is undone.
+    clock_history: Vec<Option<Clock>>,
+    /// Result of the game, once it is over.
+    pub result: Option<GameResult>,
+    /// Player whose draw offer is waiting for the opponent's reply.
+    pub draw_offer: Option<Color>,
+    /// Moment when the player to move started thinking, used for the clock.
+    turn_start: Option<Instant>,
+}
+
+/// Reason why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Timeout,
+    Stalemate,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    Agreement,
+    Abandonment,
 }
 
-#[derive(PartialEq)]
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Resignation => "resignation",
+            Termination::Timeout => "timeout",
+            Termination::Stalemate => "stalemate",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::ThreefoldRepetition => "threefold repetition",
+            Termination::FivefoldRepetition => "fivefold repetition",
+            Termination::FiftyMoveRule => "the fifty-move rule",
+            Termination::SeventyFiveMoveRule => "the seventy-five-move rule",
+            Termination::Agreement => "agreement",
+            Termination::Abandonment => "abandonment",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameResult {
-    Winner(Color),
-    Draw,
+    Winner(Color, Termination),
+    Draw(Termination),
+}
+
+impl GameResult {
+    pub fn termination(&self) -> Termination {
+        match self {
+            GameResult::Winner(_, termination) | GameResult::Draw(termination) => *termination,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameResult::Winner(Color::White, termination) => {
+                write!(f, "White wins by {}", termination)
+            }
+            GameResult::Winner(Color::Black, termination) => {
+                write!(f, "Black wins by {}", termination)
+            }
+            GameResult::Draw(termination) => write!(f, "Draw by {}", termination),
+        }
+    }
 }
 
 impl Game {
@@ -27,7 +102,12 @@ impl Game {
             game_state,
             game_display,
             controllers,
-            history: vec![], // limit of 50 moves per player without captures
+            history: vec![],
+            clock: None,
+            clock_history: vec![],
+            result: None,
+            draw_offer: None,
+            turn_start: None,
         }
     }
 
@@ -41,16 +121,40 @@ impl Game {
             return;
         };
         self.game_state = previous_state;
+        self.clock_history.pop();
+        if let Some(clock) = self.clock_history.pop() {
+            self.clock = clock;
+        }
     }
 
     pub fn execute_command(&mut self, cmd: Command) {
         match cmd {
             Command::Move(movement) => {
+                self.game_display
+                    .display_movement(&movement, &self.game_state);
                 self.history.push(self.game_state.deepclone());
+                self.clock_history.push(self.clock.clone());
+                let player = self.game_state.player_to_move;
                 self.game_state.make_movement(movement);
+                // a movement declines the opponent's offer
+                self.draw_offer = None;
+                let elapsed = self.turn_start.take().map_or(Duration::ZERO, |t| t.elapsed());
+                if let Some(clock) = &mut self.clock {
+                    clock.record_movement(player, elapsed);
+                }
             }
             Command::Save => {
-                let _ = write_game_state_to_json(&self.game_state, "game.json").unwrap();
+                if let Err(error) = write_game_to_json(self, "game.json") {
+                    println!("Could not save the game: {}", error);
+                }
+            }
+            Command::SavePgn => {
+                if let Err(error) = write_game_to_pgn(self, "game.pgn") {
+                    println!("Could not save the game: {}", error);
+                }
+            }
+            Command::ShowThreats => {
+                self.game_display.display_threats(&self.game_state);
             }
             Command::Undo => {
                 if self.history.len() < 2 {
@@ -71,32 +175,116 @@ impl Game {
                     self.undo();
                 }
             }
-            Command::Resign => todo!(),
+            Command::ClaimDraw => {
+                self.result = get_claimable_draw(&self.game_state).map(GameResult::Draw);
+            }
+            Command::OfferDraw => {
+                self.draw_offer = Some(self.game_state.player_to_move);
+                if (*self.controllers
+                    [self.game_state.player_to_move.get_opponent_color() as usize])
+                    .accept_draw(&self.game_state)
+                {
+                    self.execute_command(Command::AcceptDraw);
+                } else {
+                    self.execute_command(Command::DeclineDraw);
+                }
+            }
+            Command::AcceptDraw => {
+                self.draw_offer = None;
+                self.result = Some(GameResult::Draw(Termination::Agreement));
+            }
+            Command::DeclineDraw => {
+                self.draw_offer = None;
+                println!("The opponent declines the draw offer");
+            }
+            Command::Timeout => {
+                let player = self.game_state.player_to_move;
+                let Some(clock) = &mut self.clock else {
+                    println!("Invalid command. The game is played without a clock");
+                    return;
+                };
+                clock.remaining[player as usize] = Duration::ZERO;
+                self.result = Some(get_timeout_result(&self.game_state, player));
+            }
+            Command::Resign => {
+                let winner = self.game_state.player_to_move.get_opponent_color();
+                self.result = Some(GameResult::Winner(winner, Termination::Resignation));
+            }
         }
     }
 
     pub fn player_turn(&mut self) {
-        let cmd = self.controllers[self.game_state.player_to_move as usize]
-            .choose_command(&mut self.game_state);
-        if is_valid_cmd(&cmd, &self.game_state) {
+        let turn_start = *self.turn_start.get_or_insert_with(Instant::now);
+        let player = self.game_state.player_to_move;
+        // the controller sees the time left at this point of the turn
+        let clock = self.clock.clone().map(|mut clock| {
+            let remaining = &mut clock.remaining[player as usize];
+            *remaining = remaining.saturating_sub(turn_start.elapsed());
+            clock
+        });
+        let cmd = self.controllers[player as usize].choose_command(&mut self.game_state, clock.as_ref());
+        if let Some(clock) = &self.clock {
+            if clock.is_out_of_time(player, turn_start.elapsed()) {
+                // the player's flag fell before the command
+                self.execute_command(Command::Timeout);
+                return;
+            }
+        }
+        if is_valid_cmd(&cmd, &self.game_state, self.draw_offer) {
             self.execute_command(cmd);
         }
+        self.display();
+    }
+
+    fn display(&self) {
         self.game_display.display_game(&self.game_state);
+        if let Some(clock) = &self.clock {
+            self.game_display.display_clock(clock);
+        }
     }
 
     pub fn play(&mut self) -> GameResult {
-        self.game_display.display_game(&self.game_state);
-        while self.game_state.move_limit > 0 {
+        self.display();
+        loop {
             self.player_turn();
-            if is_in_check_mate(&self.game_state, self.game_state.player_to_move) {
-                self.game_display.display_game_over(&self.game_state);
-                return GameResult::Winner(self.game_state.player_to_move.get_opponent_color());
-            } else if is_draw(&self.game_state) {
-                self.game_display.display_game_over(&self.game_state);
-                self.game_display.display_game_over(&self.game_state);
-                return GameResult::Draw;
+            if self.result.is_none() {
+                self.result = get_game_result(&self.game_state);
+            }
+            if let Some(result) = self.result {
+                self.game_display.display_game_over(&result);
+                for controller in &mut self.controllers {
+                    controller.game_over(&self.game_state, &result);
+                }
+                return result;
             }
         }
-        return GameResult::Draw;
     }
 }
+
+/// Position and clock of a game, as saved to a file or sent to a remote player.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    #[serde(flatten)]
+    pub game_state: GameState,
+    #[serde(default)]
+    pub clock: Option<Clock>,
+}
+
+pub fn write_game_to_json(game: &Game, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(file_path)?;
+    let saved_game = SavedGame {
+        game_state: game.game_state.deepclone(),
+        clock: game.clock.clone(),
+    };
+    serde_json::to_writer_pretty(file, &saved_game)?;
+    Ok(())
+}
+
+pub fn load_game_from_json(file_path: &str) -> Result<SavedGame, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let mut saved_game: SavedGame = serde_json::from_str(&contents)?;
+    saved_game.game_state.fill_missing_fields();
+    Ok(saved_game)
+}
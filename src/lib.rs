@@ -3,5 +3,6 @@ pub mod evaluation;
 pub mod game;
 pub mod menu;
 pub mod model;
+pub mod notation;
 pub mod rules;
 pub mod view;
@@ -1,15 +1,69 @@
-mod controllers;
-mod evaluation;
-mod game;
-mod menu;
-mod model;
-mod rules;
-mod view;
+use std::env;
+use std::process;
+use std::time::Instant;
 
-use crate::game::Game;
-use menu::main_menu;
+use rust_chess::game::Game;
+use rust_chess::menu::main_menu;
+use rust_chess::model::game_state::GameState;
+use rust_chess::notation::san::movement_to_uci;
+use rust_chess::rules::perft::{divide, perft};
+
+const USAGE: &str = "Usage: rust-chess [perft|divide <depth> [FEN]]";
+
+/// Count the movements from a position to debug the move generator, split by the first movement
+/// with `divide`. The position is given in FEN, and is the initial position if omitted.
+fn perft_command(command: &str, args: &[String]) {
+    let Some(Ok(depth)) = args.first().map(|depth| depth.parse::<u32>()) else {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    };
+    let mut game_state = match args.get(1..).filter(|fen| !fen.is_empty()) {
+        Some(fen) => match GameState::from_fen(&fen.join(" ")) {
+            Ok(game_state) => game_state,
+            Err(error) => {
+                eprintln!("Invalid FEN: {}", error);
+                process::exit(1);
+            }
+        },
+        None => GameState::new(),
+    };
+    // the sliding attack tables are built on first use, which would be counted in the time
+    perft(&mut game_state, 1);
+    let now = Instant::now();
+    let nodes = if command == "divide" {
+        let mut counts: Vec<(String, u64)> = divide(&mut game_state, depth)
+            .into_iter()
+            .map(|(movement, count)| (movement_to_uci(&movement, &game_state), count))
+            .collect();
+        counts.sort();
+        for (movement, count) in &counts {
+            println!("{}: {}", movement, count);
+        }
+        println!();
+        counts.iter().map(|(_, count)| count).sum()
+    } else {
+        perft(&mut game_state, depth)
+    };
+    let elapsed = now.elapsed();
+    println!("Nodes: {}", nodes);
+    println!(
+        "Time: {} ms ({:.0} nodes/s)",
+        elapsed.as_millis(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
 
 fn main() {
-    let mut game: Game = main_menu();
-    game.play();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some(command @ ("perft" | "divide")) => perft_command(command, &args[1..]),
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+        None => {
+            let mut game: Game = main_menu();
+            game.play();
+        }
+    }
 }
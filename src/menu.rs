@@ -1,4 +1,8 @@
 use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
 
 use crate::controllers::alphabeta::AlphaBetaBot;
 use crate::controllers::controller::Controller;
@@ -6,17 +10,51 @@ use crate::controllers::local_human::LocalHuman;
 use crate::controllers::minimax::MinimaxBot;
 use crate::controllers::random_bot::RandomBot;
 use crate::controllers::remote_human::RemoteHuman;
-use crate::game::Game;
-use crate::model::game_state::{load_game_state_from_json, GameState};
+use crate::game::{load_game_from_json, Game};
+use crate::model::clock::{Clock, TimeControl};
+use crate::model::game_state::GameState;
 use crate::model::piece::Color;
+use crate::notation::pgn::load_games_from_pgn;
 use crate::view::UnicodeDisplay;
 
+/// Lines typed by the user, read on another thread so that waiting for them can time out.
+static INPUT: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+
+fn input() -> &'static Mutex<Receiver<String>> {
+    INPUT.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut buffer = String::new();
+            // stop at the end of the input
+            match io::stdin().read_line(&mut buffer) {
+                Ok(n) if n > 0 => {
+                    if sender.send(buffer).is_err() {
+                        return;
+                    }
+                }
+                _ => return,
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+/// Next line typed by the user, empty at the end of the input.
+pub fn read_line() -> String {
+    input().lock().unwrap().recv().unwrap_or_default()
+}
+
+/// Next line typed by the user, or `None` if it is not typed within the timeout.
+pub fn read_line_timeout(timeout: Duration) -> Option<String> {
+    match input().lock().unwrap().recv_timeout(timeout) {
+        Ok(line) => Some(line),
+        Err(RecvTimeoutError::Timeout) => None,
+        Err(RecvTimeoutError::Disconnected) => Some(String::new()),
+    }
+}
+
 fn read_number() -> u32 {
-    let mut buffer: String = String::new();
-    let stdin = io::stdin();
-    let Ok(_) = stdin.read_line(&mut buffer) else {
-        return read_number();
-    };
+    let buffer = read_line();
     let s = buffer.trim();
     let Ok(number) = s.parse::<u32>() else {
         return read_number();
@@ -27,11 +65,7 @@ fn read_number() -> u32 {
 pub fn accept_undo_menu() -> bool {
     println!("The opponent wants to undo the last movement");
     println!("Accept? [y/n]");
-    let mut buffer: String = String::new();
-    let stdin = io::stdin();
-    let Ok(_) = stdin.read_line(&mut buffer) else {
-        return false;
-    };
+    let buffer = read_line();
     let s = buffer.trim();
     match s {
         "y" | "Y" => true,
@@ -40,7 +74,23 @@ pub fn accept_undo_menu() -> bool {
     }
 }
 
-fn opponent_menu(game_state: &GameState, opponent_color: Color) -> Box<dyn Controller> {
+pub fn accept_draw_menu() -> bool {
+    println!("The opponent offers a draw");
+    println!("Accept? [y/n]");
+    let buffer = read_line();
+    let s = buffer.trim();
+    match s {
+        "y" | "Y" => true,
+        "n" | "N" => false,
+        _ => accept_draw_menu(),
+    }
+}
+
+fn opponent_menu(
+    game_state: &GameState,
+    clock: Option<&Clock>,
+    opponent_color: Color,
+) -> Box<dyn Controller> {
     println!("Play against:");
     println!(" 1 - Human");
     println!(" 2 - RandomBot");
@@ -62,17 +112,25 @@ fn opponent_menu(game_state: &GameState, opponent_color: Color) -> Box<dyn Contr
             println!(" 2 - Easy");
             println!(" 5 - Medium");
             println!(" * - Other");
-            Box::new(AlphaBetaBot::new(read_number()))
+            println!(" 0 - Time limit");
+            match read_number() {
+                0 => {
+                    println!("Seconds per movement:");
+                    let seconds = read_number() as u64;
+                    Box::new(AlphaBetaBot::with_time_limit(Duration::from_secs(seconds)))
+                }
+                depth => Box::new(AlphaBetaBot::new(depth)),
+            }
         }
         5 => {
             println!("Waiting for connection");
             let mut remote_human = RemoteHuman::new_listener(opponent_color);
-            remote_human.reply_to_initial_messages(game_state);
+            remote_human.reply_to_initial_messages(game_state, clock);
             Box::new(remote_human)
         }
         _ => {
             println!("Invalid option\n");
-            opponent_menu(game_state, opponent_color)
+            opponent_menu(game_state, clock, opponent_color)
         }
     };
     return controller;
@@ -106,38 +164,98 @@ fn new_game_menu() -> GameState {
     }
 }
 
-fn load_game() -> GameState {
-    println!("Type file path:");
-    let mut file_path: String = String::new();
-    let stdin: io::Stdin = io::stdin();
-    let Ok(_) = stdin.read_line(&mut file_path) else {
-        println!("Error");
-        return load_game();
-    };
-    let Ok(game_state) = load_game_state_from_json(file_path.trim()) else {
-        println!("No such file");
-        return load_game();
+fn time_control_menu() -> Option<TimeControl> {
+    println!("Time control");
+    println!(" 1 - No clock");
+    println!(" 2 - Sudden death");
+    println!(" 3 - Fischer increment");
+    println!(" 4 - Bronstein delay");
+    println!(" 5 - Moves per period");
+    let option = read_number();
+    if option == 1 {
+        return None;
+    } else if !(2..=5).contains(&option) {
+        println!("Invalid option\n");
+        return time_control_menu();
+    }
+    println!("Minutes:");
+    let time = Duration::from_secs(60 * read_number() as u64);
+    let time_control = match option {
+        2 => TimeControl::SuddenDeath { time },
+        3 => {
+            println!("Increment in seconds:");
+            let increment = Duration::from_secs(read_number() as u64);
+            TimeControl::Increment { time, increment }
+        }
+        4 => {
+            println!("Delay in seconds:");
+            let delay = Duration::from_secs(read_number() as u64);
+            TimeControl::Delay { time, delay }
+        }
+        _ => {
+            println!("Moves per period:");
+            let mut moves = read_number();
+            while moves == 0 {
+                println!("The period must have at least one move");
+                moves = read_number();
+            }
+            TimeControl::MovesPerPeriod { time, moves }
+        }
     };
-    game_state
+    Some(time_control)
+}
+
+/// Game states of a saved game, from the first position to the current one, and its clock.
+fn load_game() -> (Vec<GameState>, Option<Clock>) {
+    println!("Type file path or FEN:");
+    let file_path = read_line();
+    let file_path = file_path.trim();
+    if file_path.ends_with(".pgn") {
+        let Ok(mut games) = load_games_from_pgn(file_path) else {
+            println!("No such file");
+            return load_game();
+        };
+        return match games.next() {
+            Some(Ok(pgn_game)) => (pgn_game.game_states(), None),
+            Some(Err(error)) => {
+                println!("Invalid PGN: {}", error);
+                load_game()
+            }
+            None => {
+                println!("No games in file");
+                load_game()
+            }
+        };
+    }
+    if let Ok(saved_game) = load_game_from_json(file_path) {
+        return (vec![saved_game.game_state], saved_game.clock);
+    }
+    match GameState::from_fen(file_path) {
+        Ok(game_state) => (vec![game_state], None),
+        Err(error) => {
+            println!("No such file. Invalid FEN: {}", error);
+            load_game()
+        }
+    }
 }
 
 fn join_host() -> Game {
-    let mut buffer: String = String::new();
-    let stdin = io::stdin();
     println!("Type host address");
-    let _ = stdin.read_line(&mut buffer);
+    let buffer = read_line();
     println!("trying to connect to {}", buffer.as_str().trim());
     let Ok(mut remote_human) = RemoteHuman::new_client(buffer.as_str().trim()) else {
         // try again if connection fails
         return join_host();
     };
-    let game_state = remote_human.get_game_state();
+    let saved_game = remote_human.get_game_state();
     println!("received game state");
     let controllers: [Box<dyn Controller>; 2] = match remote_human.color {
         Color::White => [Box::new(remote_human), Box::new(LocalHuman)],
         Color::Black => [Box::new(LocalHuman), Box::new(remote_human)],
     };
-    return Game::new(game_state, Box::new(UnicodeDisplay), controllers);
+    let mut game = Game::new(saved_game.game_state, Box::new(UnicodeDisplay), controllers);
+    game.clock = saved_game.clock;
+    return game;
 }
 
 pub fn main_menu() -> Game {
@@ -146,24 +264,28 @@ pub fn main_menu() -> Game {
     println!(" 3 - Join host");
     let n = read_number();
     if n == 1 || n == 2 {
-        let game_state;
-        if n == 1 {
-            game_state = new_game_menu();
+        let (mut history, clock) = if n == 1 {
+            let game_state = new_game_menu();
+            (vec![game_state], time_control_menu().map(Clock::new))
         } else {
-            game_state = load_game();
-        }
+            load_game()
+        };
+        let game_state = history.pop().unwrap();
         let controllers: [Box<dyn Controller>; 2] = match color_menu() {
             1 => [
                 Box::new(LocalHuman),
-                opponent_menu(&game_state, Color::Black),
+                opponent_menu(&game_state, clock.as_ref(), Color::Black),
             ],
             2 => [
-                opponent_menu(&game_state, Color::White),
+                opponent_menu(&game_state, clock.as_ref(), Color::White),
                 Box::new(LocalHuman),
             ],
             _ => panic!(), // unreachable
         };
-        return Game::new(game_state, Box::new(UnicodeDisplay), controllers);
+        let mut game = Game::new(game_state, Box::new(UnicodeDisplay), controllers);
+        game.history = history;
+        game.clock = clock;
+        return game;
     } else if n == 3 {
         return join_host();
     } else {
@@ -0,0 +1,135 @@
+//! Queries about the squares attacked by the pieces of a position, for the rules,
+//! the evaluation and to show the player which pieces are threatened.
+
+use super::{
+    attacks::piece_attacks,
+    bitboard::{position_bit, positions, square_index, Bitboard},
+    game_state::GameState,
+    piece::Color,
+};
+
+/// Attacks of every piece of a position, computed once to answer many queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackMap {
+    /// Squares attacked by the piece on each square, indexed by square.
+    attacks_from: [Bitboard; 64],
+    /// Squares attacked by each color.
+    attacked: [Bitboard; 2],
+    /// Number of pieces of each color attacking each square.
+    counts: [[u8; 64]; 2],
+}
+
+impl AttackMap {
+    /// Squares attacked by the piece on the square, or none if it is empty.
+    pub fn attacks_from(&self, position: [usize; 2]) -> Bitboard {
+        self.attacks_from[square_index(position)]
+    }
+
+    /// Squares attacked by at least one piece of the color.
+    pub fn attacked_squares(&self, color: Color) -> Bitboard {
+        self.attacked[color as usize]
+    }
+
+    pub fn is_attacked(&self, position: [usize; 2], color: Color) -> bool {
+        self.attacked[color as usize] & position_bit(position) != 0
+    }
+
+    /// Number of pieces of the color attacking the square.
+    pub fn attack_count(&self, position: [usize; 2], color: Color) -> u32 {
+        self.counts[color as usize][square_index(position)] as u32
+    }
+}
+
+impl GameState {
+    /// Pieces of the color that attack the square.
+    pub fn attackers(&self, position: [usize; 2], color: Color) -> Bitboard {
+        self.attackers_with_occupancy(position, color, self.board.occupied())
+    }
+
+    /// Pieces of the color that attack the square if only the occupied squares held pieces,
+    /// to follow an exchange or a king move where some pieces have left their squares.
+    pub fn attackers_with_occupancy(
+        &self,
+        position: [usize; 2],
+        color: Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        self.board
+            .attackers(square_index(position), color, occupied)
+            & occupied
+    }
+
+    pub fn is_attacked(&self, position: [usize; 2], color: Color) -> bool {
+        self.attackers(position, color) != 0
+    }
+
+    /// Number of pieces of the color that attack the square.
+    pub fn attack_count(&self, position: [usize; 2], color: Color) -> u32 {
+        self.attackers(position, color).count_ones()
+    }
+
+    /// Pieces of the color that attack the square, directly or through other pieces of either
+    /// color that attack it too (x-rays), like a rook behind a queen on the same file.
+    /// These are the pieces that can take part in an exchange on the square.
+    pub fn xray_attackers(&self, position: [usize; 2], color: Color) -> Bitboard {
+        let mut occupied = self.board.occupied();
+        let mut attackers = 0;
+        loop {
+            // pieces uncovered once the attackers found so far have left their squares
+            let new_attackers = self.attackers_with_occupancy(position, Color::White, occupied)
+                | self.attackers_with_occupancy(position, Color::Black, occupied);
+            if new_attackers == 0 {
+                break;
+            }
+            attackers |= new_attackers;
+            occupied &= !new_attackers;
+        }
+        attackers & self.board.color(color)
+    }
+
+    /// Number of pieces of the color that attack the square, including x-rays.
+    pub fn xray_attack_count(&self, position: [usize; 2], color: Color) -> u32 {
+        self.xray_attackers(position, color).count_ones()
+    }
+
+    /// Squares attacked by the piece on the square, or none if it is empty.
+    pub fn attacks_from(&self, position: [usize; 2]) -> Bitboard {
+        let [x, y] = position;
+        match self.board[x][y] {
+            Some(piece) => piece_attacks(piece, square_index(position), self.board.occupied()),
+            None => 0,
+        }
+    }
+
+    /// Squares attacked by at least one piece of the color.
+    pub fn attacked_squares(&self, color: Color) -> Bitboard {
+        positions(self.board.color(color))
+            .map(|position| self.attacks_from(position))
+            .fold(0, |attacked, attacks| attacked | attacks)
+    }
+
+    /// Pieces of the color attacked by the opponent.
+    pub fn threatened_pieces(&self, color: Color) -> Bitboard {
+        self.board.color(color) & self.attacked_squares(color.get_opponent_color())
+    }
+
+    /// Attacks of all the pieces on the board.
+    pub fn attack_map(&self) -> AttackMap {
+        let mut attack_map = AttackMap {
+            attacks_from: [0; 64],
+            attacked: [0; 2],
+            counts: [[0; 64]; 2],
+        };
+        for position in positions(self.board.occupied()) {
+            let [x, y] = position;
+            let color = self.board[x][y].unwrap().color as usize;
+            let attacks = self.attacks_from(position);
+            attack_map.attacks_from[square_index(position)] = attacks;
+            attack_map.attacked[color] |= attacks;
+            for attacked in positions(attacks) {
+                attack_map.counts[color][square_index(attacked)] += 1;
+            }
+        }
+        attack_map
+    }
+}
@@ -0,0 +1,250 @@
+//! Precomputed attack sets of each piece type from each square.
+//!
+//! Sliding attacks use magic bitboards: the pieces on the rays of the slider are multiplied
+//! by a number of the square, chosen so that the highest bits of the product give
+//! a different index for every set of blockers that leads to different attacks.
+
+use std::sync::OnceLock;
+
+use super::bitboard::Bitboard;
+use super::piece::{Color, Piece, PieceType};
+
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+const WHITE_PAWN_OFFSETS: [(i32, i32); 2] = [(1, -1), (1, 1)];
+const BLACK_PAWN_OFFSETS: [(i32, i32); 2] = [(-1, -1), (-1, 1)];
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+const fn is_on_board(row: i32, col: i32) -> bool {
+    0 <= row && row < 8 && 0 <= col && col < 8
+}
+
+/// Squares reached with one of the offsets from each square.
+const fn leaper_attacks(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let row = (square / 8) as i32 + offsets[i].0;
+            let col = (square % 8) as i32 + offsets[i].1;
+            if is_on_board(row, col) {
+                attacks[square] |= 1 << (row * 8 + col);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+/// Indexed by color.
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&WHITE_PAWN_OFFSETS),
+    leaper_attacks(&BLACK_PAWN_OFFSETS),
+];
+
+pub fn knight_attacks(square: usize) -> Bitboard {
+    KNIGHT_ATTACKS[square]
+}
+
+pub fn king_attacks(square: usize) -> Bitboard {
+    KING_ATTACKS[square]
+}
+
+/// Squares attacked by a pawn of the color standing on the square.
+pub fn pawn_attacks(color: Color, square: usize) -> Bitboard {
+    PAWN_ATTACKS[color as usize][square]
+}
+
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    static ROOK_ATTACKS: OnceLock<SlidingAttacks> = OnceLock::new();
+    ROOK_ATTACKS
+        .get_or_init(|| SlidingAttacks::new(&ROOK_DIRECTIONS))
+        .attacks(square, occupied)
+}
+
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    static BISHOP_ATTACKS: OnceLock<SlidingAttacks> = OnceLock::new();
+    BISHOP_ATTACKS
+        .get_or_init(|| SlidingAttacks::new(&BISHOP_DIRECTIONS))
+        .attacks(square, occupied)
+}
+
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+/// Squares attacked by the piece standing on the square, with the sliding pieces
+/// blocked by the given occupied squares.
+pub fn piece_attacks(piece: Piece, square: usize, occupied: Bitboard) -> Bitboard {
+    match piece.piece_type {
+        PieceType::Pawn => pawn_attacks(piece.color, square),
+        PieceType::Knight => knight_attacks(square),
+        PieceType::Bishop => bishop_attacks(square, occupied),
+        PieceType::Rook => rook_attacks(square, occupied),
+        PieceType::Queen => queen_attacks(square, occupied),
+        PieceType::King => king_attacks(square),
+    }
+}
+
+/// Squares strictly between two squares on the same row, column or diagonal,
+/// or none if they are not aligned.
+pub fn squares_between(square1: usize, square2: usize) -> Bitboard {
+    let [bit1, bit2] = [1 << square1, 1 << square2];
+    if rook_attacks(square1, 0) & bit2 != 0 {
+        rook_attacks(square1, bit2) & rook_attacks(square2, bit1)
+    } else if bishop_attacks(square1, 0) & bit2 != 0 {
+        bishop_attacks(square1, bit2) & bishop_attacks(square2, bit1)
+    } else {
+        0
+    }
+}
+
+/// Squares reached from the square in the directions, up to the first occupied square included.
+fn sliding_attacks(square: usize, occupied: Bitboard, directions: &[(i32, i32)]) -> Bitboard {
+    let mut attacks = 0;
+    for (dx, dy) in directions {
+        let [mut row, mut col] = [(square / 8) as i32 + dx, (square % 8) as i32 + dy];
+        while is_on_board(row, col) {
+            let bit = 1 << (row * 8 + col);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            row += dx;
+            col += dy;
+        }
+    }
+    attacks
+}
+
+/// Squares whose occupancy changes the attacks: the rays without the edge of the board,
+/// since the last square of a ray is attacked whether it is occupied or not.
+fn relevant_blockers(square: usize, directions: &[(i32, i32)]) -> Bitboard {
+    let mut blockers = 0;
+    for (dx, dy) in directions {
+        let [mut row, mut col] = [(square / 8) as i32 + dx, (square % 8) as i32 + dy];
+        while is_on_board(row + dx, col + dy) {
+            blockers |= 1 << (row * 8 + col);
+            row += dx;
+            col += dy;
+        }
+    }
+    blockers
+}
+
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    /// Start of the square's attacks in the shared table.
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+/// Attacks of a sliding piece for every square and set of blockers.
+struct SlidingAttacks {
+    magics: Vec<Magic>,
+    table: Vec<Bitboard>,
+}
+
+impl SlidingAttacks {
+    /// Finds a magic number for each square by trial and error, which takes a few milliseconds.
+    fn new(directions: &[(i32, i32)]) -> Self {
+        // fixed seed, so that the tables are the same in every run
+        let mut seed = 0x9E37_79B9_7F4A_7C15;
+        let mut magics = Vec::with_capacity(64);
+        let mut table = Vec::new();
+        for square in 0..64 {
+            let mask = relevant_blockers(square, directions);
+            let shift = 64 - mask.count_ones();
+
+            // every subset of the mask (Carry-Rippler enumeration)
+            let mut subsets = Vec::new();
+            let mut subset: Bitboard = 0;
+            loop {
+                subsets.push((subset, sliding_attacks(square, subset, directions)));
+                subset = subset.wrapping_sub(mask) & mask;
+                if subset == 0 {
+                    break;
+                }
+            }
+
+            let offset = table.len();
+            table.resize(offset + (1 << mask.count_ones()), 0);
+            // attempt in which each entry of the table was last written
+            let mut filled_in = vec![0; 1 << mask.count_ones()];
+            let mut attempt = 0;
+            let magic = loop {
+                attempt += 1;
+                // numbers with few bits set are more likely to work
+                let magic = random(&mut seed) & random(&mut seed) & random(&mut seed);
+                if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+                    continue;
+                }
+                let candidate = Magic {
+                    mask,
+                    magic,
+                    shift,
+                    offset,
+                };
+                let is_valid = subsets.iter().all(|&(blockers, attacks)| {
+                    let index = candidate.index(blockers);
+                    if filled_in[index - offset] != attempt {
+                        filled_in[index - offset] = attempt;
+                        table[index] = attacks;
+                        true
+                    } else {
+                        // sets of blockers with the same attacks can share an entry
+                        table[index] == attacks
+                    }
+                });
+                if is_valid {
+                    break candidate;
+                }
+            };
+            magics.push(magic);
+        }
+        Self { magics, table }
+    }
+
+    fn attacks(&self, square: usize, occupied: Bitboard) -> Bitboard {
+        self.table[self.magics[square].index(occupied)]
+    }
+}
+
+/// Xorshift pseudorandom number generator.
+fn random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
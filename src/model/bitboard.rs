@@ -0,0 +1,183 @@
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    attacks::{bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks},
+    board::Board,
+    piece::{Color, Piece, PieceType},
+    zobrist::piece_key,
+};
+
+/// Set of squares, one bit per square: bit `row * 8 + col` (a1 = 0, h1 = 7, a8 = 56).
+pub type Bitboard = u64;
+
+/// Index of the square of a position, from 0 to 63.
+pub const fn square_index(position: [usize; 2]) -> usize {
+    position[0] * 8 + position[1]
+}
+
+/// Position of the square with the given index.
+pub const fn index_position(square: usize) -> [usize; 2] {
+    [square / 8, square % 8]
+}
+
+/// Bitboard with only the square of the position.
+pub const fn position_bit(position: [usize; 2]) -> Bitboard {
+    1 << square_index(position)
+}
+
+/// Positions of the squares in the bitboard, from a1 to h8.
+pub fn positions(bitboard: Bitboard) -> impl Iterator<Item = [usize; 2]> {
+    let mut remaining = bitboard;
+    std::iter::from_fn(move || {
+        if remaining == 0 {
+            return None;
+        }
+        let square = remaining.trailing_zeros() as usize;
+        // clear the lowest bit
+        remaining &= remaining - 1;
+        Some(index_position(square))
+    })
+}
+
+/// Placement of the pieces, stored both as an array of squares, for display and serialization,
+/// and as one bitboard per piece type and color, for fast move generation and attack detection.
+///
+/// Reads use the array through `Deref`, while changes go through `set` and `take`
+/// so that both representations stay in sync. It is serialized as the array of squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Board", into = "Board")]
+pub struct Bitboards {
+    squares: Board,
+    /// Indexed by color and piece type.
+    pieces: [[Bitboard; 6]; 2],
+    colors: [Bitboard; 2],
+    /// Zobrist hash of the pieces on their squares.
+    hash: u64,
+    /// Zobrist hash of the pawns on their squares.
+    pawn_hash: u64,
+}
+
+impl Bitboards {
+    pub const fn new(squares: Board) -> Self {
+        let mut bitboards = Self {
+            squares,
+            pieces: [[0; 6]; 2],
+            colors: [0; 2],
+            hash: 0,
+            pawn_hash: 0,
+        };
+        let mut square = 0;
+        while square < 64 {
+            if let Some(piece) = squares[square / 8][square % 8] {
+                bitboards.pieces[piece.color as usize][piece.piece_type as usize] |= 1 << square;
+                bitboards.colors[piece.color as usize] |= 1 << square;
+                let key = piece_key(piece, [square / 8, square % 8]);
+                bitboards.hash ^= key;
+                if matches!(piece.piece_type, PieceType::Pawn) {
+                    bitboards.pawn_hash ^= key;
+                }
+            }
+            square += 1;
+        }
+        bitboards
+    }
+
+    /// Places a piece on the square, or empties it, and returns the previous piece.
+    pub fn set(&mut self, position: [usize; 2], piece: Option<Piece>) -> Option<Piece> {
+        let [row, col] = position;
+        let bit = position_bit(position);
+        let previous = std::mem::replace(&mut self.squares[row][col], piece);
+        if let Some(previous) = previous {
+            self.pieces[previous.color as usize][previous.piece_type as usize] &= !bit;
+            self.colors[previous.color as usize] &= !bit;
+            self.toggle_hash(previous, position);
+        }
+        if let Some(piece) = piece {
+            self.pieces[piece.color as usize][piece.piece_type as usize] |= bit;
+            self.colors[piece.color as usize] |= bit;
+            self.toggle_hash(piece, position);
+        }
+        previous
+    }
+
+    fn toggle_hash(&mut self, piece: Piece, position: [usize; 2]) {
+        let key = piece_key(piece, position);
+        self.hash ^= key;
+        if piece.piece_type == PieceType::Pawn {
+            self.pawn_hash ^= key;
+        }
+    }
+
+    /// Removes the piece on the square.
+    pub fn take(&mut self, position: [usize; 2]) -> Option<Piece> {
+        self.set(position, None)
+    }
+
+    /// Squares of the pieces of the given type and color.
+    pub fn pieces(&self, piece: Piece) -> Bitboard {
+        self.pieces[piece.color as usize][piece.piece_type as usize]
+    }
+
+    /// Squares of the pieces of the given type, of both colors.
+    pub fn piece_type(&self, piece_type: PieceType) -> Bitboard {
+        self.pieces[0][piece_type as usize] | self.pieces[1][piece_type as usize]
+    }
+
+    /// Squares of all the pieces of the color.
+    pub fn color(&self, color: Color) -> Bitboard {
+        self.colors[color as usize]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Pieces of the color that attack the square, with the sliding pieces
+    /// blocked by the given occupied squares.
+    pub fn attackers(&self, square: usize, color: Color, occupied: Bitboard) -> Bitboard {
+        let pieces = |piece_type| self.pieces(Piece { piece_type, color });
+        let queens = pieces(PieceType::Queen);
+        // pawns attack the square from where a pawn of the other color on the square would attack
+        (pawn_attacks(color.get_opponent_color(), square) & pieces(PieceType::Pawn))
+            | (knight_attacks(square) & pieces(PieceType::Knight))
+            | (king_attacks(square) & pieces(PieceType::King))
+            | (bishop_attacks(square, occupied) & (pieces(PieceType::Bishop) | queens))
+            | (rook_attacks(square, occupied) & (pieces(PieceType::Rook) | queens))
+    }
+
+    /// Zobrist hash of the pieces on their squares, without the rest of the game state.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Zobrist hash of the pawns on their squares.
+    pub fn pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+
+    pub fn squares(&self) -> &Board {
+        &self.squares
+    }
+}
+
+impl Deref for Bitboards {
+    type Target = Board;
+
+    fn deref(&self) -> &Board {
+        &self.squares
+    }
+}
+
+impl From<Board> for Bitboards {
+    fn from(squares: Board) -> Self {
+        Self::new(squares)
+    }
+}
+
+impl From<Bitboards> for Board {
+    fn from(bitboards: Bitboards) -> Self {
+        bitboards.squares
+    }
+}
@@ -145,3 +145,24 @@ pub fn create_960_board() -> Board {
     }
     return board;
 }
+
+/// Name of a square in algebraic notation (e.g.: [0, 4] -> "e1").
+pub fn position_to_string(position: [usize; 2]) -> String {
+    let [row, col] = position;
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+/// Parse a square in algebraic notation (e.g.: "e1" -> [0, 4]).
+pub fn string_to_position(position_str: &str) -> Option<[usize; 2]> {
+    let mut chars = position_str.chars();
+    let (Some(letter), Some(number_char), None) = (chars.next(), chars.next(), chars.next()) else {
+        return None;
+    };
+    if !('a'..='h').contains(&letter) || !('1'..='8').contains(&number_char) {
+        return None;
+    }
+    Some([
+        number_char as usize - '1' as usize,
+        letter as usize - 'a' as usize,
+    ])
+}
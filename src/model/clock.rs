@@ -0,0 +1,122 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::piece::Color;
+
+/// Rules for the time available to each player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeControl {
+    /// The whole game must be played within the given time.
+    SuddenDeath { time: Duration },
+    /// Fischer increment: the increment is added to the player's time after each movement.
+    Increment { time: Duration, increment: Duration },
+    /// Bronstein delay: the time spent on a movement is given back, up to the delay.
+    Delay { time: Duration, delay: Duration },
+    /// The time is added again each time the player completes the number of movements.
+    MovesPerPeriod { time: Duration, moves: u32 },
+}
+
+impl TimeControl {
+    fn initial_time(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath { time }
+            | TimeControl::Increment { time, .. }
+            | TimeControl::Delay { time, .. }
+            | TimeControl::MovesPerPeriod { time, .. } => time,
+        }
+    }
+
+    /// Time control in the format of the PGN "TimeControl" tag, e.g. "300+5" or "40/5400".
+    pub fn to_pgn(&self) -> String {
+        match *self {
+            TimeControl::SuddenDeath { time } => format!("{}", time.as_secs()),
+            TimeControl::Increment { time, increment } => {
+                format!("{}+{}", time.as_secs(), increment.as_secs())
+            }
+            TimeControl::Delay { time, delay } => {
+                format!("{}d{}", time.as_secs(), delay.as_secs())
+            }
+            TimeControl::MovesPerPeriod { time, moves } => {
+                format!("{}/{}", moves, time.as_secs())
+            }
+        }
+    }
+}
+
+/// Remaining time of both players.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clock {
+    pub time_control: TimeControl,
+    pub remaining: [Duration; 2],
+    /// Movements made by each player in the current period.
+    pub moves_in_period: [u32; 2],
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        let time = time_control.initial_time();
+        Self {
+            time_control,
+            remaining: [time, time],
+            moves_in_period: [0, 0],
+        }
+    }
+
+    pub fn get_remaining(&self, player: Color) -> Duration {
+        self.remaining[player as usize]
+    }
+
+    /// Whether the player's flag falls after thinking for the elapsed time.
+    pub fn is_out_of_time(&self, player: Color, elapsed: Duration) -> bool {
+        elapsed >= self.remaining[player as usize]
+    }
+
+    /// Discount the time spent by the player on a movement and apply the time control's bonus.
+    pub fn record_movement(&mut self, player: Color, elapsed: Duration) {
+        let remaining = &mut self.remaining[player as usize];
+        *remaining = remaining.saturating_sub(elapsed);
+        match self.time_control {
+            TimeControl::SuddenDeath { .. } => {}
+            TimeControl::Increment { increment, .. } => *remaining += increment,
+            TimeControl::Delay { delay, .. } => *remaining += elapsed.min(delay),
+            TimeControl::MovesPerPeriod { time, moves } => {
+                let moves_in_period = &mut self.moves_in_period[player as usize];
+                *moves_in_period += 1;
+                if *moves_in_period >= moves {
+                    *moves_in_period = 0;
+                    *remaining += time;
+                }
+            }
+        }
+    }
+}
+
+/// Duration as "h:mm:ss", "m:ss", or with tenths of second when less than 10 seconds are left.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        )
+    } else if seconds >= 10 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("0:{:02}.{}", seconds, duration.subsec_millis() / 100)
+    }
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "White {} | Black {}",
+            format_duration(self.get_remaining(Color::White)),
+            format_duration(self.get_remaining(Color::Black))
+        )
+    }
+}
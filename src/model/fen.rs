@@ -0,0 +1,304 @@
+use std::fmt;
+
+use super::{
+    board::{position_to_string, string_to_position, Board},
+    game_state::GameState,
+    piece::{Color, Piece, PieceType},
+};
+
+/// Reasons why a FEN string could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    WrongNumberOfFields(usize),
+    WrongNumberOfRanks(usize),
+    InvalidRank(String),
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassantSquare(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::WrongNumberOfFields(n) => {
+                write!(f, "expected 4 or 6 space separated fields, found {}", n)
+            }
+            FenError::WrongNumberOfRanks(n) => write!(f, "expected 8 ranks, found {}", n),
+            FenError::InvalidRank(rank) => write!(f, "rank '{}' does not have 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece character '{}'", c),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move '{}'", s),
+            FenError::InvalidCastlingRights(s) => write!(f, "invalid castling rights '{}'", s),
+            FenError::InvalidEnPassantSquare(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::InvalidHalfmoveClock(s) => write!(f, "invalid halfmove clock '{}'", s),
+            FenError::InvalidFullmoveNumber(s) => write!(f, "invalid fullmove number '{}'", s),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+fn back_rank(player: Color) -> usize {
+    match player {
+        Color::White => 0,
+        Color::Black => 7,
+    }
+}
+
+fn parse_placement(placement: &str) -> Result<Board, FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongNumberOfRanks(ranks.len()));
+    }
+    let mut board: Board = [[None; 8]; 8];
+    for (i, rank) in ranks.iter().enumerate() {
+        let row = 7 - i;
+        let mut col = 0;
+        for c in rank.chars() {
+            if let Some(n) = c.to_digit(10) {
+                if n == 0 || n > 8 {
+                    return Err(FenError::InvalidRank(rank.to_string()));
+                }
+                col += n as usize;
+            } else {
+                let color = if c.is_ascii_uppercase() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let Ok(piece) = Piece::from_char(c, color) else {
+                    return Err(FenError::InvalidPiece(c));
+                };
+                if col >= 8 {
+                    return Err(FenError::InvalidRank(rank.to_string()));
+                }
+                board[row][col] = Some(piece);
+                col += 1;
+            }
+            if col > 8 {
+                return Err(FenError::InvalidRank(rank.to_string()));
+            }
+        }
+        if col != 8 {
+            return Err(FenError::InvalidRank(rank.to_string()));
+        }
+    }
+    Ok(board)
+}
+
+fn find_king_on_back_rank(board: &Board, player: Color) -> Option<[usize; 2]> {
+    let row = back_rank(player);
+    (0..8)
+        .find(|&col| {
+            board[row][col]
+                == Some(Piece {
+                    piece_type: PieceType::King,
+                    color: player,
+                })
+        })
+        .map(|col| [row, col])
+}
+
+fn is_rook_of(board: &Board, position: [usize; 2], player: Color) -> bool {
+    board[position[0]][position[1]]
+        == Some(Piece {
+            piece_type: PieceType::Rook,
+            color: player,
+        })
+}
+
+/// Outermost rook of the player on one side of the king, as assumed by the K/Q/k/q notation.
+fn outermost_rook(board: &Board, player: Color, king_col: usize, king_side: bool) -> Option<usize> {
+    let row = back_rank(player);
+    if king_side {
+        ((king_col + 1)..8)
+            .rev()
+            .find(|&col| is_rook_of(board, [row, col], player))
+    } else {
+        (0..king_col).find(|&col| is_rook_of(board, [row, col], player))
+    }
+}
+
+/// Parse castling rights in standard, X-FEN (KQkq) or Shredder-FEN (HAha) notation.
+fn parse_castling(castling: &str, game_state: &mut GameState) -> Result<(), FenError> {
+    let error = || FenError::InvalidCastlingRights(castling.to_string());
+    for player in [Color::White, Color::Black] {
+        game_state.king_initial_positions[player as usize] =
+            find_king_on_back_rank(&game_state.board, player);
+        game_state.set_can_castle(player, true, false);
+        game_state.set_can_castle(player, false, false);
+    }
+    game_state.rook_initial_positions = [[None, None], [None, None]];
+    if castling == "-" {
+        return Ok(());
+    }
+    for c in castling.chars() {
+        let player = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let Some([row, king_col]) = game_state.get_king_initial_position(player) else {
+            return Err(error());
+        };
+        let (king_side, rook_col) = match c.to_ascii_lowercase() {
+            'k' => (
+                true,
+                outermost_rook(&game_state.board, player, king_col, true),
+            ),
+            'q' => (
+                false,
+                outermost_rook(&game_state.board, player, king_col, false),
+            ),
+            'a'..='h' => {
+                let col = c.to_ascii_lowercase() as usize - 'a' as usize;
+                if !is_rook_of(&game_state.board, [row, col], player) || col == king_col {
+                    return Err(error());
+                }
+                (col > king_col, Some(col))
+            }
+            _ => return Err(error()),
+        };
+        let Some(rook_col) = rook_col else {
+            return Err(error());
+        };
+        if game_state.can_castle(player, king_side) {
+            // the same right was given twice
+            return Err(error());
+        }
+        game_state.set_can_castle(player, king_side, true);
+        game_state.rook_initial_positions[king_side as usize][player as usize] =
+            Some([row, rook_col]);
+    }
+    Ok(())
+}
+
+/// Castling rights in X-FEN: K/Q/k/q unless another rook stands further out, then the rook's file.
+fn castling_to_string(game_state: &GameState) -> String {
+    let mut castling = String::new();
+    for player in [Color::White, Color::Black] {
+        let Some([_, king_col]) = game_state.get_king_initial_position(player) else {
+            continue;
+        };
+        for king_side in [true, false] {
+            if !game_state.can_castle(player, king_side) {
+                continue;
+            }
+            let Some([_, rook_col]) = game_state.get_rook_initial_position(player, king_side)
+            else {
+                continue;
+            };
+            let mut c = if outermost_rook(&game_state.board, player, king_col, king_side)
+                == Some(rook_col)
+            {
+                if king_side {
+                    'k'
+                } else {
+                    'q'
+                }
+            } else {
+                (b'a' + rook_col as u8) as char
+            };
+            if player == Color::White {
+                c = c.to_ascii_uppercase();
+            }
+            castling.push(c);
+        }
+    }
+    if castling.is_empty() {
+        castling.push('-');
+    }
+    castling
+}
+
+impl GameState {
+    /// Create a game state from a position in Forsyth-Edwards Notation.
+    ///
+    /// The halfmove clock and fullmove number may be omitted, in which case they default to 0 and 1.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::WrongNumberOfFields(fields.len()));
+        }
+        let mut game_state = GameState::new();
+        game_state.board = parse_placement(fields[0])?.into();
+        game_state.player_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            s => return Err(FenError::InvalidSideToMove(s.to_string())),
+        };
+        parse_castling(fields[2], &mut game_state)?;
+        if fields[3] != "-" {
+            let error = || FenError::InvalidEnPassantSquare(fields[3].to_string());
+            let [row, col] = string_to_position(fields[3]).ok_or_else(error)?;
+            // the square behind a pawn that has just moved two squares
+            let (expected_row, pawn_row) = match game_state.player_to_move {
+                Color::White => (5, 4),
+                Color::Black => (2, 3),
+            };
+            let pawn = Piece {
+                piece_type: PieceType::Pawn,
+                color: game_state.player_to_move.get_opponent_color(),
+            };
+            if row != expected_row || game_state.board[pawn_row][col] != Some(pawn) {
+                return Err(error());
+            }
+            game_state.en_passant_square = Some([row, col]);
+        }
+        if fields.len() == 6 {
+            let Ok(halfmove_clock) = fields[4].parse::<u32>() else {
+                return Err(FenError::InvalidHalfmoveClock(fields[4].to_string()));
+            };
+            game_state.halfmove_clock = halfmove_clock;
+            match fields[5].parse::<u32>() {
+                Ok(n) if n >= 1 => game_state.fullmove_number = n,
+                _ => return Err(FenError::InvalidFullmoveNumber(fields[5].to_string())),
+            }
+        }
+        Ok(game_state)
+    }
+
+    /// Position in Forsyth-Edwards Notation. Chess960 castling rights are written as X-FEN.
+    pub fn to_fen(&self) -> String {
+        let mut ranks = vec![];
+        for row in (0..8).rev() {
+            let mut rank = String::new();
+            let mut empty_squares = 0;
+            for col in 0..8 {
+                match self.board[row][col] {
+                    Some(piece) => {
+                        if empty_squares > 0 {
+                            rank.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+                        rank.push(piece.to_ascii());
+                    }
+                    None => empty_squares += 1,
+                }
+            }
+            if empty_squares > 0 {
+                rank.push_str(&empty_squares.to_string());
+            }
+            ranks.push(rank);
+        }
+        let side_to_move = match self.player_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        let en_passant = match self.en_passant_square {
+            Some(position) => position_to_string(position),
+            None => "-".to_string(),
+        };
+        format!(
+            "{} {} {} {} {} {}",
+            ranks.join("/"),
+            side_to_move,
+            castling_to_string(self),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
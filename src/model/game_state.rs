@@ -6,24 +6,74 @@ use std::path::Path;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    board::{create_960_board, Board, INIT_POSITIONS},
+    bitboard::{positions, Bitboards},
+    board::{create_960_board, INIT_POSITIONS},
     movement::Movement,
     piece::{Color, Piece, PieceType},
 };
 
 /// Representation of the game state including all variables that are necessary to continue the match.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
-    pub board: Board,
+    pub board: Bitboards,
     pub player_to_move: Color,
     pub last_move: Option<Movement>,
+    /// Square skipped by a pawn that has just advanced two squares, where it can be taken en passant.
+    #[serde(default)]
+    pub en_passant_square: Option<[usize; 2]>,
     pub white_can_castle_queen_side: bool,
     pub white_can_castle_king_side: bool,
     pub black_can_castle_queen_side: bool,
     pub black_can_castle_king_side: bool,
     pub king_initial_positions: [Option<[usize; 2]>; 2],
     pub rook_initial_positions: [[Option<[usize; 2]>; 2]; 2],
-    pub move_limit: i32,
+    /// Number of halfmoves since the last capture or pawn movement.
+    #[serde(default)]
+    pub halfmove_clock: u32,
+    #[serde(default = "default_fullmove_number")]
+    pub fullmove_number: u32,
+    /// Hashes of the previous positions since the last capture, pawn movement or castling.
+    #[serde(default)]
+    pub position_history: Vec<u64>,
+}
+
+/// State before a movement made with `GameState::make_move`, to take it back.
+#[derive(Debug, Clone)]
+pub struct Undo {
+    movement: Movement,
+    moved_piece: Option<Piece>,
+    captured_piece: Option<Piece>,
+    /// Square of the captured piece, which is not the destination when capturing en passant.
+    captured_position: [usize; 2],
+    /// Row of the king and the rook before castling.
+    castling_rank: Option<(usize, [Option<Piece>; 8])>,
+    /// Queen and king side for White, then for Black.
+    castling_rights: [bool; 4],
+    en_passant_square: Option<[usize; 2]>,
+    last_move: Option<Movement>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// Positions forgotten by an irreversible movement.
+    position_history: Option<Vec<u64>>,
+}
+
+fn default_fullmove_number() -> u32 {
+    1
+}
+
+/// Two game states are equal if they describe the same position, regardless of how it was reached.
+impl PartialEq for GameState {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.player_to_move == other.player_to_move
+            && self.en_passant_square == other.en_passant_square
+            && self.white_can_castle_queen_side == other.white_can_castle_queen_side
+            && self.white_can_castle_king_side == other.white_can_castle_king_side
+            && self.black_can_castle_queen_side == other.black_can_castle_queen_side
+            && self.black_can_castle_king_side == other.black_can_castle_king_side
+            && self.king_initial_positions == other.king_initial_positions
+            && self.rook_initial_positions == other.rook_initial_positions
+    }
 }
 
 pub fn write_game_state_to_json(
@@ -40,7 +90,8 @@ pub fn load_game_state_from_json(file_path: &str) -> Result<GameState, Box<dyn s
     let mut file = File::open(path)?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
-    let game_state: GameState = serde_json::from_str(&contents)?;
+    let mut game_state: GameState = serde_json::from_str(&contents)?;
+    game_state.fill_missing_fields();
     Ok(game_state)
 }
 
@@ -48,16 +99,19 @@ impl GameState {
     /// Initialization for standard chess match.
     pub const fn new() -> Self {
         Self {
-            board: INIT_POSITIONS,
+            board: Bitboards::new(INIT_POSITIONS),
             player_to_move: Color::White,
             last_move: None,
+            en_passant_square: None,
             white_can_castle_queen_side: true,
             white_can_castle_king_side: true,
             black_can_castle_king_side: true,
             black_can_castle_queen_side: true,
             king_initial_positions: [Some([0, 4]), Some([7, 4])],
             rook_initial_positions: [[Some([0, 0]), Some([7, 0])], [Some([0, 7]), Some([7, 7])]],
-            move_limit: 100,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            position_history: Vec::new(),
         }
     }
 
@@ -83,56 +137,42 @@ impl GameState {
             }
         }
         Self {
-            board: initial_positions,
+            board: initial_positions.into(),
             player_to_move: Color::White,
             last_move: None,
+            en_passant_square: None,
             white_can_castle_queen_side: true,
             white_can_castle_king_side: true,
             black_can_castle_king_side: true,
             black_can_castle_queen_side: true,
             king_initial_positions: king_positions,
             rook_initial_positions: [queen_rook_positions, king_rook_positions],
-            move_limit: 100,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            position_history: Vec::new(),
         }
     }
 
     pub fn deepclone(&self) -> GameState {
         GameState {
-            board: self.deepclone_board(),
+            board: self.board,
             player_to_move: self.player_to_move.clone(),
             last_move: self.last_move.clone(),
+            en_passant_square: self.en_passant_square,
             white_can_castle_queen_side: self.white_can_castle_queen_side,
             white_can_castle_king_side: self.white_can_castle_king_side,
             black_can_castle_queen_side: self.black_can_castle_queen_side,
             black_can_castle_king_side: self.black_can_castle_king_side,
             king_initial_positions: self.king_initial_positions, // constant, doesnt need cloning
             rook_initial_positions: self.rook_initial_positions, // constant, doesnt need cloning
-            move_limit: self.move_limit,
-        }
-    }
-
-    fn deepclone_board(&self) -> Board {
-        let mut new_board = [[None; 8]; 8];
-
-        for i in 0..8 {
-            for j in 0..8 {
-                new_board[i][j] = self.board[i][j].clone();
-            }
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            position_history: self.position_history.clone(),
         }
-        new_board
     }
 
     pub fn get_piece_positions(&self, piece: Piece) -> Vec<[usize; 2]> {
-        let mut results = vec![];
-        for i in 0..8 {
-            for j in 0..8 {
-                match self.board[i][j] {
-                    Some(piece2) if piece == piece2 => results.push([i, j]),
-                    _ => continue,
-                }
-            }
-        }
-        results
+        positions(self.board.pieces(piece)).collect()
     }
 
     pub fn get_rook_initial_position(&self, player: Color, king_side: bool) -> Option<[usize; 2]> {
@@ -143,107 +183,166 @@ impl GameState {
         return self.king_initial_positions[player as usize];
     }
 
+    pub fn can_castle(&self, player: Color, king_side: bool) -> bool {
+        match (player, king_side) {
+            (Color::White, true) => self.white_can_castle_king_side,
+            (Color::White, false) => self.white_can_castle_queen_side,
+            (Color::Black, true) => self.black_can_castle_king_side,
+            (Color::Black, false) => self.black_can_castle_queen_side,
+        }
+    }
+
+    pub fn set_can_castle(&mut self, player: Color, king_side: bool, value: bool) {
+        match (player, king_side) {
+            (Color::White, true) => self.white_can_castle_king_side = value,
+            (Color::White, false) => self.white_can_castle_queen_side = value,
+            (Color::Black, true) => self.black_can_castle_king_side = value,
+            (Color::Black, false) => self.black_can_castle_queen_side = value,
+        }
+    }
+
     /// Verify if after a certain movement it is still possible to castle and update attributes accordingly.
     fn update_can_castle(&mut self, movement: &Movement) {
-        let Movement::Normal { from: source, to: destination } = movement else {
+        let Movement::Normal {
+            from: source,
+            to: destination,
+            ..
+        } = movement
+        else {
             return;
         };
-        let Some(white_king_position) = self.get_king_initial_position(Color::White) else {
-            self.white_can_castle_king_side = false;
-            self.white_can_castle_queen_side = false;
-            return;
-        };
-        let Some(black_king_position) = self.get_king_initial_position(Color::Black) else {
-            self.black_can_castle_king_side = false;
-            self.black_can_castle_queen_side = false;
-            return;
-        };
-        if self.player_to_move == Color::White {
-            if self.white_can_castle_king_side {
-                if source == &white_king_position
-                    || source == &self.get_rook_initial_position(Color::White, true).unwrap()
-                {
-                    self.white_can_castle_king_side = false;
-                }
-            }
-            if self.white_can_castle_queen_side {
-                if source == &white_king_position
-                    || source == &self.get_rook_initial_position(Color::White, false).unwrap()
-                {
-                    self.white_can_castle_queen_side = false;
-                }
-            }
-            if destination == &self.get_rook_initial_position(Color::Black, false).unwrap() {
-                self.black_can_castle_queen_side = false;
-            }
-            if destination == &self.get_rook_initial_position(Color::Black, true).unwrap() {
-                self.black_can_castle_king_side = false;
-            }
-        } else {
-            if self.black_can_castle_king_side {
-                if source == &black_king_position
-                    || source == &self.get_rook_initial_position(Color::Black, true).unwrap()
-                {
-                    self.black_can_castle_king_side = false;
+        for player in [Color::White, Color::Black] {
+            let Some(king_position) = self.get_king_initial_position(player) else {
+                self.set_can_castle(player, true, false);
+                self.set_can_castle(player, false, false);
+                continue;
+            };
+            for king_side in [true, false] {
+                if !self.can_castle(player, king_side) {
+                    continue;
                 }
-            }
-            if self.black_can_castle_queen_side {
-                if source == &black_king_position
-                    || source == &self.get_rook_initial_position(Color::Black, false).unwrap()
+                let rook_position = self.get_rook_initial_position(player, king_side);
+                if *source == king_position
+                    || Some(*source) == rook_position
+                    || Some(*destination) == rook_position
                 {
-                    self.black_can_castle_queen_side = false;
+                    // the king or the rook moved, or the rook was captured
+                    self.set_can_castle(player, king_side, false);
                 }
             }
-            if destination == &self.get_rook_initial_position(Color::White, false).unwrap() {
-                self.white_can_castle_queen_side = false;
-            }
-            if destination == &self.get_rook_initial_position(Color::White, true).unwrap() {
-                self.white_can_castle_king_side = false;
+        }
+    }
+
+    fn update_halfmove_clock(&mut self, movement: &Movement) {
+        self.halfmove_clock += 1;
+        if let Movement::Normal {
+            from: [x, y],
+            to: [x2, y2],
+            ..
+        } = movement
+        {
+            let is_pawn =
+                matches!(self.board[*x][*y], Some(piece) if piece.piece_type == PieceType::Pawn);
+            if is_pawn || self.board[*x2][*y2].is_some() {
+                // pawn movement or capture
+                self.halfmove_clock = 0;
             }
         }
     }
 
-    fn update_move_limit(&mut self, movement: &Movement) {
-        self.move_limit -= 1;
+    /// Whether the positions before the movement can't be repeated after it.
+    fn is_irreversible(&self, movement: &Movement) -> bool {
         match movement {
-            // check if it is a capture
             Movement::Normal {
-                to: [x, y],
-                from: _,
+                from: [x, y],
+                to: [x2, y2],
+                ..
             } => {
-                if self.board[*x][*y] != None {
-                    self.move_limit = 100;
-                }
+                let is_pawn =
+                    matches!(self.board[*x][*y], Some(piece) if piece.piece_type == PieceType::Pawn);
+                is_pawn || self.board[*x2][*y2].is_some()
             }
-            _ => {}
+            Movement::CastleKingSide(_) | Movement::CastleQueenSide(_) => true,
+        }
+    }
+
+    /// Remember the current position before a movement, or forget the previous positions
+    /// if the movement is irreversible, since they can no longer be repeated.
+    fn update_position_history(&mut self, movement: &Movement) {
+        if self.is_irreversible(movement) {
+            self.position_history.clear();
+        } else {
+            self.position_history.push(self.zobrist_hash());
+        }
+    }
+
+    /// Number of times the current position occurred since the last irreversible movement.
+    pub fn repetition_count(&self) -> usize {
+        let hash = self.zobrist_hash();
+        1 + self.position_history.iter().filter(|&&h| h == hash).count()
+    }
+
+    /// Complete a game state read from a file saved by an older version.
+    pub(crate) fn fill_missing_fields(&mut self) {
+        if self.en_passant_square.is_none() {
+            // files saved before the en passant square was stored
+            self.en_passant_square = self.en_passant_square_from_last_move();
+        }
+    }
+
+    /// Square skipped by the last movement if it was a pawn advancing two squares.
+    fn en_passant_square_from_last_move(&self) -> Option<[usize; 2]> {
+        let Some(Movement::Normal { from, to, .. }) = self.last_move else {
+            return None;
+        };
+        match self.board[to[0]][to[1]] {
+            Some(piece)
+                if piece.piece_type == PieceType::Pawn
+                    && from[1] == to[1]
+                    && from[0].abs_diff(to[0]) == 2 =>
+            {
+                Some([(from[0] + to[0]) / 2, to[1]])
+            }
+            _ => None,
         }
     }
 
     /// Update game state with a movement.
     pub fn make_movement(&mut self, movement: Movement) {
-        self.update_move_limit(&movement);
+        if self.player_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.update_halfmove_clock(&movement);
+        self.update_position_history(&movement);
+        let en_passant_square = mem::take(&mut self.en_passant_square);
         match movement {
             Movement::Normal {
                 from: source,
                 to: destination,
+                promotion,
             } => {
                 self.update_can_castle(&movement);
                 let [x, y] = source;
                 let [x2, y2] = destination;
-                if (y != y2 && (x2 == 4 || x2 == 5))
-                    && self.board[x][y].unwrap().piece_type == PieceType::Pawn
-                    && self.board[x2][y2] == None
-                {
+                let is_pawn =
+                    matches!(self.board[x][y], Some(piece) if piece.piece_type == PieceType::Pawn);
+                if is_pawn && Some(destination) == en_passant_square {
                     // en passant
-                    self.board[x][y2] = None;
+                    self.board.take([x, y2]);
                 }
-                self.board[x2][y2] = mem::take(&mut self.board[x][y]);
-                if (x2 == 0 || x2 == 7) && self.board[x2][y2].unwrap().piece_type == PieceType::Pawn
-                {
-                    self.board[x2][y2] = Some(Piece {
-                        piece_type: PieceType::Queen,
-                        color: self.player_to_move,
-                    }); // promote the pawn
+                if is_pawn && x.abs_diff(x2) == 2 {
+                    self.en_passant_square = Some([(x + x2) / 2, y]);
+                }
+                let piece = self.board.take(source);
+                self.board.set(destination, piece);
+                if (x2 == 0 || x2 == 7) && is_pawn {
+                    self.board.set(
+                        destination,
+                        Some(Piece {
+                            piece_type: promotion.unwrap_or(PieceType::Queen),
+                            color: self.player_to_move,
+                        }),
+                    ); // promote the pawn
                 }
                 self.player_to_move = self.player_to_move.get_opponent_color();
                 self.last_move = Some(movement);
@@ -253,6 +352,93 @@ impl GameState {
         }
     }
 
+    /// Make a movement in place, returning what is needed to take it back with `unmake_move`.
+    /// Cheaper than `clone_and_move` when the previous state is needed again.
+    pub fn make_move(&mut self, movement: Movement) -> Undo {
+        let (moved_piece, captured_piece, captured_position, castling_rank) = match movement {
+            Movement::Normal { from, to, .. } => {
+                let moved_piece = self.board[from[0]][from[1]];
+                let is_pawn =
+                    matches!(moved_piece, Some(piece) if piece.piece_type == PieceType::Pawn);
+                let captured_position = if is_pawn && self.en_passant_square == Some(to) {
+                    // the pawn taken en passant is behind the destination square
+                    [from[0], to[1]]
+                } else {
+                    to
+                };
+                let [x, y] = captured_position;
+                (moved_piece, self.board[x][y], captured_position, None)
+            }
+            Movement::CastleKingSide(player) | Movement::CastleQueenSide(player) => {
+                // the king and the rook only move on their initial row
+                let row = self
+                    .get_king_initial_position(player)
+                    .map_or(0, |position| position[0]);
+                (None, None, [0, 0], Some((row, self.board[row])))
+            }
+        };
+        let position_history = if self.is_irreversible(&movement) {
+            Some(mem::take(&mut self.position_history))
+        } else {
+            None
+        };
+        let undo = Undo {
+            movement: movement.clone(),
+            moved_piece,
+            captured_piece,
+            captured_position,
+            castling_rank,
+            castling_rights: [
+                self.white_can_castle_queen_side,
+                self.white_can_castle_king_side,
+                self.black_can_castle_queen_side,
+                self.black_can_castle_king_side,
+            ],
+            en_passant_square: self.en_passant_square,
+            last_move: self.last_move.clone(),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            position_history,
+        };
+        self.make_movement(movement);
+        undo
+    }
+
+    /// Take back the last movement made with `make_move`, restoring the previous state.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        match undo.movement {
+            Movement::Normal { from, to, .. } => {
+                self.board.take(to);
+                self.board.set(from, undo.moved_piece);
+                self.board.set(undo.captured_position, undo.captured_piece);
+            }
+            Movement::CastleKingSide(_) | Movement::CastleQueenSide(_) => {
+                if let Some((row, pieces)) = undo.castling_rank {
+                    for (col, piece) in pieces.into_iter().enumerate() {
+                        self.board.set([row, col], piece);
+                    }
+                }
+            }
+        }
+        self.player_to_move = self.player_to_move.get_opponent_color();
+        [
+            self.white_can_castle_queen_side,
+            self.white_can_castle_king_side,
+            self.black_can_castle_queen_side,
+            self.black_can_castle_king_side,
+        ] = undo.castling_rights;
+        self.en_passant_square = undo.en_passant_square;
+        self.last_move = undo.last_move;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        match undo.position_history {
+            Some(position_history) => self.position_history = position_history,
+            None => {
+                self.position_history.pop();
+            }
+        }
+    }
+
     fn set_curr_player_cant_castle(&mut self) {
         if self.player_to_move == Color::White {
             self.white_can_castle_king_side = false;
@@ -268,23 +454,26 @@ impl GameState {
         let Some([king_row, king_col]) = self.get_king_initial_position(self.player_to_move) else {
             return;
         };
-        for col in king_col..8 {
-            match self.board[king_row][col] {
-                Some(piece) if piece.piece_type == PieceType::Rook => {
-                    self.board[king_row][col] = None;
-                }
-                _ => {}
-            }
+        let rook_position = self.get_rook_initial_position(self.player_to_move, true);
+        // both pieces are taken before placing them, since their squares can overlap
+        self.board.take([king_row, king_col]);
+        if let Some(rook_position) = rook_position {
+            self.board.take(rook_position);
         }
-        self.board[king_row][king_col] = None;
-        self.board[king_row][6] = Some(Piece {
-            piece_type: PieceType::King,
-            color: self.player_to_move,
-        });
-        self.board[king_row][5] = Some(Piece {
-            piece_type: PieceType::Rook,
-            color: self.player_to_move,
-        });
+        self.board.set(
+            [king_row, 6],
+            Some(Piece {
+                piece_type: PieceType::King,
+                color: self.player_to_move,
+            }),
+        );
+        self.board.set(
+            [king_row, 5],
+            Some(Piece {
+                piece_type: PieceType::Rook,
+                color: self.player_to_move,
+            }),
+        );
 
         self.last_move = Some(Movement::CastleKingSide(self.player_to_move));
         self.player_to_move = self.player_to_move.get_opponent_color();
@@ -296,39 +485,33 @@ impl GameState {
             println!("Tried to castle without having a king");
             panic!();
         };
-        for col in 0..king_col {
-            match self.board[king_row][col] {
-                Some(piece) if piece.piece_type == PieceType::Rook => {
-                    self.board[king_row][col] = None;
-                }
-                _ => {}
-            }
+        let rook_position = self.get_rook_initial_position(self.player_to_move, false);
+        // both pieces are taken before placing them, since their squares can overlap
+        self.board.take([king_row, king_col]);
+        if let Some(rook_position) = rook_position {
+            self.board.take(rook_position);
         }
-        self.board[king_row][king_col] = None;
-        self.board[king_row][2] = Some(Piece {
-            piece_type: PieceType::King,
-            color: self.player_to_move,
-        });
-        self.board[king_row][3] = Some(Piece {
-            piece_type: PieceType::Rook,
-            color: self.player_to_move,
-        });
+        self.board.set(
+            [king_row, 2],
+            Some(Piece {
+                piece_type: PieceType::King,
+                color: self.player_to_move,
+            }),
+        );
+        self.board.set(
+            [king_row, 3],
+            Some(Piece {
+                piece_type: PieceType::Rook,
+                color: self.player_to_move,
+            }),
+        );
 
         self.last_move = Some(Movement::CastleQueenSide(self.player_to_move));
         self.player_to_move = self.player_to_move.get_opponent_color();
     }
 
     pub fn get_positions_of_color(&self, color: Color) -> Vec<[usize; 2]> {
-        let mut results = vec![];
-        for i in 0..8 {
-            for j in 0..8 {
-                match self.board[i][j] {
-                    Some(piece) if piece.color == color => results.push([i, j]),
-                    _ => continue,
-                }
-            }
-        }
-        results
+        positions(self.board.color(color)).collect()
     }
 
     /// Create new game state and make a movement on it.
@@ -1,4 +1,10 @@
-mod board;
+pub mod attack_map;
+pub mod attacks;
+pub mod bitboard;
+pub mod board;
+pub mod clock;
+pub mod fen;
 pub mod game_state;
 pub mod movement;
 pub mod piece;
+pub mod zobrist;
@@ -1,11 +1,18 @@
 use crate::model::{game_state::GameState, piece::Color, piece::Piece, piece::PieceType};
+use crate::notation::san::movement_to_san;
 use serde::{Deserialize, Serialize};
 
 #[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum Movement {
     CastleKingSide(Color),
     CastleQueenSide(Color),
-    Normal { from: [usize; 2], to: [usize; 2] },
+    Normal {
+        from: [usize; 2],
+        to: [usize; 2],
+        /// Piece chosen when a pawn reaches the last row. A queen if not indicated.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        promotion: Option<PieceType>,
+    },
 }
 
 impl Movement {
@@ -28,4 +35,9 @@ impl Movement {
             }
         }
     }
+
+    /// Movement in Standard Algebraic Notation, given the game state before the movement.
+    pub fn to_san(&self, game_state: &GameState) -> String {
+        movement_to_san(self, game_state)
+    }
 }
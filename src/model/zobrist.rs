@@ -0,0 +1,91 @@
+use super::{
+    game_state::GameState,
+    piece::{Color, Piece, PieceType},
+};
+
+const SIDE_KEY: usize = 2 * 6 * 64;
+const CASTLING_KEYS: usize = SIDE_KEY + 1;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const NUMBER_OF_KEYS: usize = EN_PASSANT_KEYS + 8;
+
+/// Random numbers for each piece on each square, side to move, castling right and en passant file.
+const KEYS: [u64; NUMBER_OF_KEYS] = generate_keys();
+
+/// Fixed pseudo-random keys (splitmix64), so that hashes are the same between executions.
+const fn generate_keys() -> [u64; NUMBER_OF_KEYS] {
+    let mut keys = [0; NUMBER_OF_KEYS];
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut i = 0;
+    while i < NUMBER_OF_KEYS {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Key of a piece on a square. The hash of the board is kept up to date by `Bitboards`.
+pub const fn piece_key(piece: Piece, position: [usize; 2]) -> u64 {
+    let [row, col] = position;
+    let piece_index = piece.color as usize * 6 + piece.piece_type as usize;
+    KEYS[piece_index * 64 + row * 8 + col]
+}
+
+impl GameState {
+    /// Zobrist hash of the position: board, player to move, castling rights and en passant rights.
+    /// Equal positions have equal hashes, independently of the movements that led to them.
+    ///
+    /// The hash of the board is updated with each change of a square, so this takes constant time.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = self.board.hash();
+        if self.player_to_move == Color::Black {
+            hash ^= KEYS[SIDE_KEY];
+        }
+        for (i, (player, king_side)) in [
+            (Color::White, true),
+            (Color::White, false),
+            (Color::Black, true),
+            (Color::Black, false),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if self.can_castle(player, king_side) {
+                hash ^= KEYS[CASTLING_KEYS + i];
+            }
+        }
+        if let Some([_, col]) = self.en_passant_square {
+            if self.en_passant_capture_is_possible() {
+                hash ^= KEYS[EN_PASSANT_KEYS + col];
+            }
+        }
+        hash
+    }
+
+    /// Zobrist hash of the pawns only, to cache evaluations of the pawn structure.
+    /// Like the hash of the board, it is kept up to date by `Bitboards`.
+    pub fn pawn_hash(&self) -> u64 {
+        self.board.pawn_hash()
+    }
+
+    /// Whether a pawn of the player to move stands next to the pawn that can be taken en passant.
+    /// Otherwise the en passant square does not distinguish the position from others.
+    fn en_passant_capture_is_possible(&self) -> bool {
+        let Some([row, col]) = self.en_passant_square else {
+            return false;
+        };
+        let pawn_row = match self.player_to_move {
+            Color::White => row - 1,
+            Color::Black => row + 1,
+        };
+        let pawn = Some(Piece {
+            piece_type: PieceType::Pawn,
+            color: self.player_to_move,
+        });
+        (col > 0 && self.board[pawn_row][col - 1] == pawn)
+            || (col < 7 && self.board[pawn_row][col + 1] == pawn)
+    }
+}
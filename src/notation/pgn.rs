@@ -0,0 +1,514 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::controllers::controller::Controller;
+use crate::game::{Game, GameResult};
+use crate::model::{fen::FenError, game_state::GameState, movement::Movement, piece::Color};
+use crate::rules::cmd_validator::is_valid_movement;
+use crate::rules::game_over::get_game_result;
+use crate::view::GameDisplay;
+
+use super::san::{movement_to_san, parse_san, MoveParseError};
+
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Today's date in the PGN format "YYYY.MM.DD".
+fn current_date() -> String {
+    let Ok(elapsed) = SystemTime::now().duration_since(UNIX_EPOCH) else {
+        return "????.??.??".to_string();
+    };
+    // convert days since 1970-01-01 to a civil date
+    let days = (elapsed.as_secs() / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Result token of the game: "1-0", "0-1", "1/2-1/2" or "*" if the game is not over.
+pub fn result_token(game_state: &GameState) -> &'static str {
+    game_result_token(get_game_result(game_state))
+}
+
+fn game_result_token(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::Winner(Color::White, _)) => "1-0",
+        Some(GameResult::Winner(Color::Black, _)) => "0-1",
+        Some(GameResult::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// Game states before each movement of the game, paired with the movement played.
+fn played_movements(game: &Game) -> Vec<(&GameState, String)> {
+    let mut movements = vec![];
+    for (i, state_before) in game.history.iter().enumerate() {
+        let state_after = game.history.get(i + 1).unwrap_or(&game.game_state);
+        if let Some(movement) = &state_after.last_move {
+            movements.push((state_before, movement_to_san(movement, state_before)));
+        }
+    }
+    movements
+}
+
+/// Whether the king or a castling rook starts outside the standard squares.
+fn is_chess960(game_state: &GameState) -> bool {
+    let standard_start = GameState::new();
+    let king_moved = (0..2).any(|i| {
+        game_state.king_initial_positions[i].is_some()
+            && game_state.king_initial_positions[i] != standard_start.king_initial_positions[i]
+    });
+    let rook_moved = (0..2).any(|i| {
+        (0..2).any(|j| {
+            game_state.rook_initial_positions[i][j].is_some()
+                && game_state.rook_initial_positions[i][j]
+                    != standard_start.rook_initial_positions[i][j]
+        })
+    });
+    king_moved || rook_moved
+}
+
+fn write_tag(pgn: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+}
+
+/// Wrap movetext tokens in lines of at most 80 characters.
+fn wrap_tokens(tokens: Vec<String>) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        text.push_str(&token);
+    }
+    text
+}
+
+/// Full game record in Portable Game Notation.
+pub fn write_pgn(game: &Game) -> String {
+    let initial_state = game.history.first().unwrap_or(&game.game_state);
+    let result = game_result_token(game.result.or_else(|| get_game_result(&game.game_state)));
+    let standard_start = GameState::new();
+
+    let mut pgn = String::new();
+    write_tag(&mut pgn, "Event", "Casual game");
+    write_tag(&mut pgn, "Site", "?");
+    write_tag(&mut pgn, "Date", &current_date());
+    write_tag(&mut pgn, "Round", "-");
+    write_tag(&mut pgn, "White", "?");
+    write_tag(&mut pgn, "Black", "?");
+    write_tag(&mut pgn, "Result", result);
+    if is_chess960(initial_state) {
+        write_tag(&mut pgn, "Variant", "Chess960");
+    }
+    if let Some(clock) = &game.clock {
+        write_tag(&mut pgn, "TimeControl", &clock.time_control.to_pgn());
+    }
+    if initial_state.to_fen() != standard_start.to_fen() {
+        write_tag(&mut pgn, "SetUp", "1");
+        write_tag(&mut pgn, "FEN", &initial_state.to_fen());
+    }
+    pgn.push('\n');
+
+    let mut tokens = vec![];
+    for (i, (state_before, san)) in played_movements(game).into_iter().enumerate() {
+        if state_before.player_to_move == Color::White {
+            tokens.push(format!("{}.", state_before.fullmove_number));
+        } else if i == 0 {
+            tokens.push(format!("{}...", state_before.fullmove_number));
+        }
+        tokens.push(san);
+    }
+    tokens.push(result.to_string());
+    pgn.push_str(&wrap_tokens(tokens));
+    pgn.push('\n');
+    pgn
+}
+
+pub fn write_game_to_pgn(game: &Game, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(file_path)?;
+    file.write_all(write_pgn(game).as_bytes())?;
+    Ok(())
+}
+
+/// Reasons why a game could not be read from PGN.
+#[derive(Debug)]
+pub enum PgnError {
+    Io(std::io::Error),
+    InvalidTag(String),
+    InvalidFen(FenError),
+    InvalidMovement {
+        move_number: u32,
+        error: MoveParseError,
+    },
+    UnexpectedToken(String),
+    UnexpectedEndOfInput,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Io(error) => write!(f, "{}", error),
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair '{}'", tag),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::InvalidMovement { move_number, error } => {
+                write!(f, "move {}: {}", move_number, error)
+            }
+            PgnError::UnexpectedToken(token) => write!(f, "unexpected token '{}'", token),
+            PgnError::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<std::io::Error> for PgnError {
+    fn from(error: std::io::Error) -> Self {
+        PgnError::Io(error)
+    }
+}
+
+/// Game read from PGN: its tags, starting position and main line.
+/// The movements of the variations are checked when reading, but not kept.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub initial_state: GameState,
+    pub movements: Vec<Movement>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Game states from the starting position to the final position, one per movement.
+    pub fn game_states(&self) -> Vec<GameState> {
+        let mut game_states = vec![self.initial_state.clone()];
+        for movement in &self.movements {
+            let next_state = game_states.last().unwrap().clone_and_move(movement.clone());
+            game_states.push(next_state);
+        }
+        game_states
+    }
+
+    /// Create a game at the final position, which can be continued or undone.
+    pub fn into_game(
+        self,
+        game_display: Box<dyn GameDisplay>,
+        controllers: [Box<dyn Controller>; 2],
+    ) -> Game {
+        let mut history = self.game_states();
+        let game_state = history.pop().unwrap();
+        let mut game = Game::new(game_state, game_display, controllers);
+        game.history = history;
+        game
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Symbol(String),
+    Period,
+    Asterisk,
+    Nag,
+    VariationStart,
+    VariationEnd,
+}
+
+fn is_result(symbol: &str) -> bool {
+    matches!(symbol, "1-0" | "0-1" | "1/2-1/2")
+}
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)
+}
+
+/// Reads the games of a PGN file one at a time, so large databases don't need to fit in memory.
+pub struct PgnReader<R: BufRead> {
+    input: R,
+    line: Vec<char>,
+    position: usize,
+    pending_token: Option<Token>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            line: vec![],
+            position: 0,
+            pending_token: None,
+        }
+    }
+
+    /// Next character of the input, or None at the end of the input.
+    fn next_char(&mut self) -> Result<Option<char>, PgnError> {
+        while self.position >= self.line.len() {
+            let mut buffer = String::new();
+            if self.input.read_line(&mut buffer)? == 0 {
+                return Ok(None);
+            }
+            if buffer.starts_with('%') {
+                // escaped line
+                continue;
+            }
+            self.line = buffer.chars().collect();
+            self.position = 0;
+        }
+        self.position += 1;
+        Ok(Some(self.line[self.position - 1]))
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.line.get(self.position).copied()
+    }
+
+    fn read_tag(&mut self) -> Result<Token, PgnError> {
+        let mut tag = String::new();
+        loop {
+            match self.next_char()? {
+                None => return Err(PgnError::UnexpectedEndOfInput),
+                Some(']') => break,
+                Some('"') => {
+                    // string value, which can contain ']' and escaped quotes
+                    tag.push('"');
+                    loop {
+                        match self.next_char()? {
+                            None => return Err(PgnError::UnexpectedEndOfInput),
+                            Some('\\') => match self.next_char()? {
+                                Some(c) => tag.push(c),
+                                None => return Err(PgnError::UnexpectedEndOfInput),
+                            },
+                            Some('"') => break,
+                            Some(c) => tag.push(c),
+                        }
+                    }
+                    tag.push('"');
+                }
+                Some(c) => tag.push(c),
+            }
+        }
+        let invalid_tag = || PgnError::InvalidTag(tag.clone());
+        let (name, value) = tag
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(invalid_tag)?;
+        let value = value.trim();
+        if name.is_empty() || !value.starts_with('"') || !value.ends_with('"') || value.len() < 2 {
+            return Err(invalid_tag());
+        }
+        Ok(Token::Tag(
+            name.to_string(),
+            value[1..value.len() - 1].to_string(),
+        ))
+    }
+
+    /// Next token, skipping whitespace and comments. None at the end of the input.
+    fn next_token(&mut self) -> Result<Option<Token>, PgnError> {
+        if let Some(token) = self.pending_token.take() {
+            return Ok(Some(token));
+        }
+        loop {
+            let Some(c) = self.next_char()? else {
+                return Ok(None);
+            };
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                '{' => {
+                    while !matches!(self.next_char()?, Some('}') | None) {}
+                    continue;
+                }
+                ';' => {
+                    self.position = self.line.len();
+                    continue;
+                }
+                '[' => self.read_tag()?,
+                '.' => Token::Period,
+                '*' => Token::Asterisk,
+                '(' => Token::VariationStart,
+                ')' => Token::VariationEnd,
+                '$' => {
+                    while self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+                        self.position += 1;
+                    }
+                    Token::Nag
+                }
+                '!' | '?' => {
+                    while self.peek_char().is_some_and(|c| c == '!' || c == '?') {
+                        self.position += 1;
+                    }
+                    Token::Nag
+                }
+                c if is_symbol_char(c) => {
+                    let mut symbol = c.to_string();
+                    while let Some(c) = self.peek_char().filter(|c| is_symbol_char(*c)) {
+                        symbol.push(c);
+                        self.position += 1;
+                    }
+                    Token::Symbol(symbol)
+                }
+                c => return Err(PgnError::UnexpectedToken(c.to_string())),
+            };
+            return Ok(Some(token));
+        }
+    }
+
+    /// Skip the rest of the current game after an error.
+    fn skip_game(&mut self) {
+        while let Ok(Some(token)) = self.next_token() {
+            match token {
+                Token::Asterisk => return,
+                Token::Symbol(symbol) if is_result(&symbol) => return,
+                Token::Tag(..) => {
+                    self.pending_token = Some(token);
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_game(&mut self) -> Result<Option<PgnGame>, PgnError> {
+        let mut tags = vec![];
+        let first_token = loop {
+            match self.next_token()? {
+                None if tags.is_empty() => return Ok(None),
+                None => return Err(PgnError::UnexpectedEndOfInput),
+                Some(Token::Tag(name, value)) => tags.push((name, value)),
+                Some(token) => break token,
+            }
+        };
+
+        let initial_state = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => GameState::from_fen(fen).map_err(PgnError::InvalidFen)?,
+            None => GameState::new(),
+        };
+        let mut game = PgnGame {
+            tags,
+            initial_state: initial_state.clone(),
+            movements: vec![],
+            result: "*".to_string(),
+        };
+        let mut game_state = initial_state;
+        // position before the last movement of the line, where a variation of that movement starts
+        let mut previous_state: Option<GameState> = None;
+        // lines interrupted by a variation, which continue at its end
+        let mut parent_lines: Vec<(GameState, Option<GameState>)> = vec![];
+        let mut token = Some(first_token);
+        loop {
+            match token {
+                None => break,
+                Some(Token::Tag(..)) if !parent_lines.is_empty() => {
+                    // next game started inside a variation
+                    self.pending_token = token;
+                    return Err(PgnError::UnexpectedEndOfInput);
+                }
+                Some(Token::Tag(..)) => {
+                    // next game started without a result token
+                    self.pending_token = token;
+                    break;
+                }
+                Some(Token::VariationStart) => {
+                    let Some(branch_state) = previous_state.clone() else {
+                        return Err(PgnError::UnexpectedToken("(".to_string()));
+                    };
+                    let line_state = mem::replace(&mut game_state, branch_state);
+                    parent_lines.push((line_state, previous_state.take()));
+                }
+                Some(Token::VariationEnd) => {
+                    let Some((line_state, line_previous_state)) = parent_lines.pop() else {
+                        return Err(PgnError::UnexpectedToken(")".to_string()));
+                    };
+                    game_state = line_state;
+                    previous_state = line_previous_state;
+                }
+                Some(Token::Asterisk) if !parent_lines.is_empty() => {
+                    return Err(PgnError::UnexpectedToken("*".to_string()))
+                }
+                Some(Token::Asterisk) => break,
+                Some(Token::Symbol(symbol)) if is_result(&symbol) => {
+                    if !parent_lines.is_empty() {
+                        return Err(PgnError::UnexpectedToken(symbol));
+                    }
+                    game.result = symbol;
+                    break;
+                }
+                Some(Token::Symbol(symbol)) if symbol.chars().all(|c| c.is_ascii_digit()) => {
+                    // move number indication
+                }
+                Some(Token::Symbol(symbol)) => {
+                    let invalid_movement = |error| PgnError::InvalidMovement {
+                        move_number: game_state.fullmove_number,
+                        error,
+                    };
+                    // movements of the variations are checked like those of the main line
+                    let movement = parse_san(&symbol, &game_state).map_err(invalid_movement)?;
+                    if !is_valid_movement(&movement, &game_state) {
+                        return Err(invalid_movement(MoveParseError::IllegalMovement(symbol)));
+                    }
+                    previous_state = Some(game_state.clone());
+                    game_state.make_movement(movement.clone());
+                    if parent_lines.is_empty() {
+                        game.movements.push(movement);
+                    }
+                }
+                Some(Token::Period) | Some(Token::Nag) => {}
+            }
+            token = self.next_token()?;
+        }
+        if !parent_lines.is_empty() {
+            return Err(PgnError::UnexpectedEndOfInput);
+        }
+        Ok(Some(game))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_game() {
+            Ok(game) => game.map(Ok),
+            Err(error) => {
+                self.skip_game();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Read all games of a PGN text.
+pub fn read_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    PgnReader::new(pgn.as_bytes()).collect()
+}
+
+/// Open a PGN file and stream its games.
+pub fn load_games_from_pgn(file_path: &str) -> Result<PgnReader<BufReader<File>>, PgnError> {
+    Ok(PgnReader::new(BufReader::new(File::open(file_path)?)))
+}
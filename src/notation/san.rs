@@ -0,0 +1,374 @@
+use std::fmt;
+
+use crate::model::{
+    board::{position_to_string, string_to_position},
+    game_state::GameState,
+    movement::Movement,
+    piece::PieceType,
+};
+use crate::rules::cmd_validator::is_in_check;
+use crate::rules::move_generator::generate_movements;
+
+pub fn piece_type_to_san(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "K",
+        PieceType::Queen => "Q",
+        PieceType::Rook => "R",
+        PieceType::Bishop => "B",
+        PieceType::Knight => "N",
+        PieceType::Pawn => "",
+    }
+}
+
+/// File and/or rank of the source square needed to distinguish the movement from other
+/// legal movements of the same piece type to the same destination.
+fn disambiguation(from: [usize; 2], to: [usize; 2], game_state: &GameState) -> String {
+    let piece_type = game_state.board[from[0]][from[1]].unwrap().piece_type;
+    let mut same_file = false;
+    let mut same_rank = false;
+    let mut ambiguous = false;
+    for movement in generate_movements(game_state) {
+        let Movement::Normal {
+            from: other_from,
+            to: other_to,
+            ..
+        } = movement
+        else {
+            continue;
+        };
+        if other_to != to || other_from == from {
+            continue;
+        }
+        match game_state.board[other_from[0]][other_from[1]] {
+            Some(piece) if piece.piece_type == piece_type => {
+                ambiguous = true;
+                same_rank |= other_from[0] == from[0];
+                same_file |= other_from[1] == from[1];
+            }
+            _ => {}
+        }
+    }
+    let square = position_to_string(from);
+    if !ambiguous {
+        String::new()
+    } else if !same_file {
+        square[0..1].to_string()
+    } else if !same_rank {
+        square[1..2].to_string()
+    } else {
+        square
+    }
+}
+
+/// Check (+) or check mate (#) indicator for the position after the movement.
+fn check_suffix(movement: &Movement, game_state: &GameState) -> &'static str {
+    let next_state = game_state.clone_and_move(movement.clone());
+    if !is_in_check(&next_state, next_state.player_to_move) {
+        ""
+    } else if generate_movements(&next_state).is_empty() {
+        "#"
+    } else {
+        "+"
+    }
+}
+
+/// Write a legal movement in Standard Algebraic Notation (e.g.: "Nbd7", "exd6", "e8=Q+", "O-O").
+pub fn movement_to_san(movement: &Movement, game_state: &GameState) -> String {
+    let mut san = match movement {
+        Movement::CastleKingSide(_) => "O-O".to_string(),
+        Movement::CastleQueenSide(_) => "O-O-O".to_string(),
+        Movement::Normal {
+            from,
+            to,
+            promotion,
+        } => {
+            let piece = movement.get_piece(game_state);
+            // a diagonal pawn movement is always a capture, even en passant
+            let is_capture = game_state.board[to[0]][to[1]].is_some()
+                || (piece.piece_type == PieceType::Pawn && from[1] != to[1]);
+            let mut san = String::new();
+            if piece.piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push_str(&position_to_string(*from)[0..1]);
+                }
+            } else {
+                san.push_str(piece_type_to_san(piece.piece_type));
+                san.push_str(&disambiguation(*from, *to, game_state));
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&position_to_string(*to));
+            if piece.piece_type == PieceType::Pawn && (to[0] == 0 || to[0] == 7) {
+                san.push('=');
+                san.push_str(piece_type_to_san(promotion.unwrap_or(PieceType::Queen)));
+            }
+            san
+        }
+    };
+    san.push_str(check_suffix(movement, game_state));
+    san
+}
+
+/// Write a movement as UCI coordinates (e.g.: "g1f3", "e7e8q", "e1g1"). Castling is written as
+/// the king moving to its destination, or capturing its own rook when it moves less than two
+/// squares (Fischer's random chess), so that it can't be mistaken for a king movement.
+pub fn movement_to_uci(movement: &Movement, game_state: &GameState) -> String {
+    match movement {
+        Movement::Normal {
+            from,
+            to,
+            promotion,
+        } => {
+            let mut uci = position_to_string(*from) + &position_to_string(*to);
+            if let Some(piece_type) = promotion {
+                uci.push_str(&piece_type_to_san(*piece_type).to_ascii_lowercase());
+            }
+            uci
+        }
+        Movement::CastleKingSide(player) | Movement::CastleQueenSide(player) => {
+            let king_side = matches!(movement, Movement::CastleKingSide(_));
+            let Some(from) = game_state.get_king_initial_position(*player) else {
+                return movement_to_san(movement, game_state);
+            };
+            let king_to = [from[0], if king_side { 6 } else { 2 }];
+            let to = match game_state.get_rook_initial_position(*player, king_side) {
+                Some(rook_from) if from[1].abs_diff(king_to[1]) < 2 => rook_from,
+                _ => king_to,
+            };
+            position_to_string(from) + &position_to_string(to)
+        }
+    }
+}
+
+/// Reasons why a movement could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    InvalidSyntax(String),
+    IllegalMovement(String),
+    AmbiguousMovement(String),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveParseError::InvalidSyntax(s) => write!(f, "'{}' is not a valid movement", s),
+            MoveParseError::IllegalMovement(s) => write!(f, "'{}' is not a legal movement", s),
+            MoveParseError::AmbiguousMovement(s) => {
+                write!(f, "'{}' matches more than one legal movement", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveParseError {}
+
+fn piece_type_from_san(c: char) -> Option<PieceType> {
+    match c {
+        'K' => Some(PieceType::King),
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None,
+    }
+}
+
+/// Read a movement in Standard Algebraic Notation. Check and annotation suffixes are ignored.
+pub fn parse_san(san: &str, game_state: &GameState) -> Result<Movement, MoveParseError> {
+    let invalid_syntax = || MoveParseError::InvalidSyntax(san.to_string());
+    let illegal = || MoveParseError::IllegalMovement(san.to_string());
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    let legal_movements = generate_movements(game_state);
+
+    let castle = match text {
+        "O-O" | "0-0" => Some(Movement::CastleKingSide(game_state.player_to_move)),
+        "O-O-O" | "0-0-0" => Some(Movement::CastleQueenSide(game_state.player_to_move)),
+        _ => None,
+    };
+    if let Some(movement) = castle {
+        if !legal_movements.contains(&movement) {
+            return Err(illegal());
+        }
+        return Ok(movement);
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+    let piece_type = match chars.first().and_then(|c| piece_type_from_san(*c)) {
+        Some(piece_type) => {
+            chars.remove(0);
+            piece_type
+        }
+        None => PieceType::Pawn,
+    };
+    // promotion suffix: "e8=Q" or "e8Q"
+    let mut promotion = None;
+    if let Some(&last) = chars.last() {
+        if let Some(piece_type2) = piece_type_from_san(last) {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            if piece_type != PieceType::Pawn {
+                return Err(invalid_syntax());
+            }
+            promotion = Some(piece_type2);
+        }
+    }
+    if chars.len() < 2 {
+        return Err(invalid_syntax());
+    }
+    let destination_str: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let Some(destination) = string_to_position(&destination_str) else {
+        return Err(invalid_syntax());
+    };
+    let is_capture = chars.last() == Some(&'x');
+    if is_capture {
+        chars.pop();
+    }
+    // remaining characters disambiguate the source square
+    let (source_col, source_row) = match chars.as_slice() {
+        [] => (None, None),
+        [c @ 'a'..='h'] => (Some(*c as usize - 'a' as usize), None),
+        [r @ '1'..='8'] => (None, Some(*r as usize - '1' as usize)),
+        [c @ 'a'..='h', r @ '1'..='8'] => (
+            Some(*c as usize - 'a' as usize),
+            Some(*r as usize - '1' as usize),
+        ),
+        _ => return Err(invalid_syntax()),
+    };
+
+    let candidates: Vec<Movement> = legal_movements
+        .into_iter()
+        .filter(|movement| match movement {
+            Movement::Normal {
+                from,
+                to,
+                promotion: promotion2,
+            } => {
+                *to == destination
+                    && (promotion.is_none() || promotion == *promotion2)
+                    && game_state.board[from[0]][from[1]].map(|piece| piece.piece_type)
+                        == Some(piece_type)
+                    && source_col.is_none_or(|col| col == from[1])
+                    && source_row.is_none_or(|row| row == from[0])
+            }
+            _ => false,
+        })
+        .collect();
+    match candidates.as_slice() {
+        [] => Err(illegal()),
+        [movement] => {
+            let Movement::Normal { from, to, .. } = movement else {
+                return Err(illegal());
+            };
+            let captures = game_state.board[to[0]][to[1]].is_some()
+                || (piece_type == PieceType::Pawn && from[1] != to[1]);
+            // captures must be marked with 'x', and only captures
+            if is_capture != captures {
+                return Err(illegal());
+            }
+            Ok(movement.clone())
+        }
+        _ => Err(MoveParseError::AmbiguousMovement(san.to_string())),
+    }
+}
+
+/// Read a movement written with source and destination squares: long algebraic notation
+/// ("e2-e4", "Ng1xf3", "e7-e8=Q") or UCI ("e2e4", "e7e8q", "e1g1").
+/// Returns None if the text is not written in one of those notations.
+fn parse_coordinates(
+    text: &str,
+    game_state: &GameState,
+) -> Option<Result<Movement, MoveParseError>> {
+    let illegal = || MoveParseError::IllegalMovement(text.to_string());
+    let mut chars: Vec<char> = text
+        .trim_end_matches(['+', '#', '!', '?'])
+        .chars()
+        .collect();
+    let piece_type = match chars.first().and_then(|c| piece_type_from_san(*c)) {
+        Some(piece_type) => {
+            chars.remove(0);
+            Some(piece_type)
+        }
+        None => None,
+    };
+    let promotion = match chars.last() {
+        Some(c) if chars.len() > 4 && "QRBNqrbn".contains(*c) => {
+            let promotion = piece_type_from_san(c.to_ascii_uppercase());
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            promotion
+        }
+        _ => None,
+    };
+    let separator = match chars.len() {
+        4 => None,
+        5 if chars[2] == '-' || chars[2] == 'x' => Some(chars.remove(2)),
+        _ => return None,
+    };
+    let from = string_to_position(&chars[0..2].iter().collect::<String>())?;
+    let to = string_to_position(&chars[2..4].iter().collect::<String>())?;
+
+    let Some(piece) = game_state.board[from[0]][from[1]] else {
+        return Some(Err(illegal()));
+    };
+    if piece.color != game_state.player_to_move
+        || piece_type.is_some_and(|piece_type| piece_type != piece.piece_type)
+    {
+        return Some(Err(illegal()));
+    }
+    let legal_movements = generate_movements(game_state);
+    if piece.piece_type == PieceType::King {
+        // castling is written as the king moving two squares, or capturing its own rook
+        let captures_own_rook = game_state.board[to[0]][to[1]].is_some_and(|piece2| {
+            piece2.color == piece.color && piece2.piece_type == PieceType::Rook
+        });
+        let castle = if captures_own_rook || from[1].abs_diff(to[1]) >= 2 {
+            if to[1] > from[1] {
+                Some(Movement::CastleKingSide(piece.color))
+            } else {
+                Some(Movement::CastleQueenSide(piece.color))
+            }
+        } else {
+            None
+        };
+        if let Some(castle) = castle {
+            if separator == Some('x') || !legal_movements.contains(&castle) {
+                return Some(Err(illegal()));
+            }
+            return Some(Ok(castle));
+        }
+    }
+
+    let movement = Movement::Normal {
+        from,
+        to,
+        promotion,
+    };
+    let is_promotion = piece.piece_type == PieceType::Pawn && (to[0] == 0 || to[0] == 7);
+    if is_promotion && promotion.is_none() {
+        // the promotion piece must be chosen
+        return Some(Err(MoveParseError::AmbiguousMovement(text.to_string())));
+    }
+    let captures = game_state.board[to[0]][to[1]].is_some()
+        || (piece.piece_type == PieceType::Pawn && from[1] != to[1]);
+    if !legal_movements.contains(&movement)
+        || (separator == Some('x') && !captures)
+        || (separator == Some('-') && captures)
+    {
+        return Some(Err(illegal()));
+    }
+    Some(Ok(movement))
+}
+
+/// Read a movement in SAN, long algebraic notation or UCI coordinates.
+pub fn parse_movement(text: &str, game_state: &GameState) -> Result<Movement, MoveParseError> {
+    let text = text.trim();
+    match parse_coordinates(text, game_state) {
+        Some(result) => result,
+        None => parse_san(text, game_state),
+    }
+}
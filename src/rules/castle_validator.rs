@@ -1,134 +1,67 @@
 use crate::model::{
+    attacks::squares_between,
+    bitboard::{position_bit, positions, square_index},
     game_state::GameState,
-    movement::Movement,
-    piece::{Color, PieceType},
+    piece::{Piece, PieceType},
 };
 
-use super::cmd_validator::is_in_check;
-
-fn castle_king_side_destination_is_free(game_state: &GameState) -> bool {
-    let king_row = game_state
-        .get_king_initial_position(game_state.player_to_move)
-        .unwrap()[0];
-    for col in [5, 6] {
-        if game_state.board[king_row][col] != None {
-            // TODO: Edge case when the other rook is in the king's destination
-            if game_state.board[king_row][col].unwrap().piece_type != PieceType::King
-                && game_state.board[king_row][col].unwrap().piece_type != PieceType::Rook
-            {
-                return false;
-            }
-        }
-    }
-    true
-}
-
-fn castle_queen_side_destination_is_free(game_state: &GameState) -> bool {
-    let king_row = game_state
-        .get_king_initial_position(game_state.player_to_move)
-        .unwrap()[0];
-    for col in [2, 3] {
-        if game_state.board[king_row][col] != None {
-            // TODO: Edge case when the other rook is in the king's destination
-            if game_state.board[king_row][col].unwrap().piece_type != PieceType::King
-                && game_state.board[king_row][col].unwrap().piece_type != PieceType::Rook
-            {
-                return false;
-            }
-        }
+/// Destinations of the king and the rook when castling, which are the same as in standard chess
+/// for any initial position (Fischer's random chess).
+fn castle_destinations(row: usize, king_side: bool) -> ([usize; 2], [usize; 2]) {
+    if king_side {
+        ([row, 6], [row, 5])
+    } else {
+        ([row, 2], [row, 3])
     }
-    true
 }
 
-pub fn king_castle_is_valid(game_state: &GameState) -> bool {
-    if !((game_state.player_to_move == Color::White && game_state.white_can_castle_king_side)
-        || (game_state.player_to_move == Color::Black && game_state.black_can_castle_king_side))
-    {
+/// The player to move can castle if the king and the rook didn't move, the squares they cross
+/// are empty, and the king is not in check and doesn't cross nor land on an attacked square.
+fn castle_is_valid(game_state: &GameState, king_side: bool) -> bool {
+    let player = game_state.player_to_move;
+    if !game_state.can_castle(player, king_side) {
         return false;
     }
-    if game_state.get_king_initial_position(game_state.player_to_move) == None {
+    let (Some(king_from), Some(rook_from)) = (
+        game_state.get_king_initial_position(player),
+        game_state.get_rook_initial_position(player, king_side),
+    ) else {
         return false;
-    }
-    if free_space_between_rook_and_king(game_state, true)
-        && castle_king_side_destination_is_free(game_state)
-    {
-        let game_state2 =
-            game_state.clone_and_move(Movement::CastleKingSide(game_state.player_to_move));
-        if is_in_check(game_state, game_state.player_to_move) {
-            return false;
-        }
-        if is_in_check(&game_state2, game_state.player_to_move) {
-            return false;
-        }
-        return true;
-    }
-    false
-}
-
-pub fn queen_castle_is_valid(game_state: &GameState) -> bool {
-    if !((game_state.player_to_move == Color::White && game_state.white_can_castle_queen_side)
-        || (game_state.player_to_move == Color::Black && game_state.black_can_castle_queen_side))
-    {
+    };
+    let board = &game_state.board;
+    let is_piece = |[x, y]: [usize; 2], piece_type| {
+        board[x][y]
+            == Some(Piece {
+                piece_type,
+                color: player,
+            })
+    };
+    if !is_piece(king_from, PieceType::King) || !is_piece(rook_from, PieceType::Rook) {
         return false;
     }
-    if game_state.get_king_initial_position(game_state.player_to_move) == None {
+
+    let (king_to, rook_to) = castle_destinations(king_from[0], king_side);
+    // squares crossed by each piece, including its destination
+    let path = |from: [usize; 2], to: [usize; 2]| {
+        squares_between(square_index(from), square_index(to)) | position_bit(to)
+    };
+    let king_path = path(king_from, king_to) | position_bit(king_from);
+    let rook_path = path(rook_from, rook_to);
+    // the king and the rook can cross each other's square
+    let occupied = board.occupied() & !position_bit(king_from) & !position_bit(rook_from);
+    if (king_path | rook_path) & occupied != 0 {
         return false;
     }
+    // the rook leaving its square can uncover an attack on the king's path
+    let opponent = player.get_opponent_color();
+    positions(king_path)
+        .all(|position| game_state.attackers_with_occupancy(position, opponent, occupied) == 0)
+}
 
-    if free_space_between_rook_and_king(game_state, false)
-        && castle_queen_side_destination_is_free(game_state)
-    {
-        let game_state2 =
-            game_state.clone_and_move(Movement::CastleQueenSide(game_state.player_to_move));
-        if is_in_check(&game_state2, game_state.player_to_move) {
-            return false;
-        }
-        if is_in_check(game_state, game_state.player_to_move) {
-            return false;
-        }
-        return true;
-    }
-    false
+pub fn king_castle_is_valid(game_state: &GameState) -> bool {
+    castle_is_valid(game_state, true)
 }
 
-fn free_space_between_rook_and_king(game_state: &GameState, king_side: bool) -> bool {
-    let mut rook_column = 0;
-    let Some([_, king_col]) = game_state.get_king_initial_position(game_state.player_to_move) else {
-        return false;
-    };
-    if king_side {
-        for col in king_col + 1..8 {
-            match game_state.board[0][col] {
-                Some(piece) if piece.piece_type == PieceType::Rook => {
-                    rook_column = col;
-                    break;
-                }
-                _ => {}
-            }
-        }
-    } else {
-        for col in 0..king_col {
-            match game_state.board[0][col] {
-                Some(piece) if piece.piece_type == PieceType::Rook => {
-                    rook_column = col;
-                    break;
-                }
-                _ => {}
-            }
-        }
-    }
-    if king_col < rook_column {
-        for i in king_col + 1..rook_column {
-            if game_state.board[king_col][i] != None {
-                return false;
-            }
-        }
-    } else {
-        for i in rook_column + 1..king_col {
-            if game_state.board[king_col][i] != None {
-                return false;
-            }
-        }
-    }
-    true
+pub fn queen_castle_is_valid(game_state: &GameState) -> bool {
+    castle_is_valid(game_state, false)
 }
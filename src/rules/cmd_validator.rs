@@ -1,12 +1,15 @@
 use crate::controllers::controller::Command;
+use crate::model::bitboard::positions;
 use crate::model::{
     game_state::GameState, movement::Movement, piece::Color, piece::Piece, piece::PieceType,
 };
 
 use super::castle_validator::{king_castle_is_valid, queen_castle_is_valid};
+use super::game_over::get_claimable_draw;
+use super::move_generator::PROMOTION_PIECE_TYPES;
 
 fn is_valid_movement_for_rook(movement: &Movement, game_state: &GameState) -> bool {
-    let Movement::Normal { from, to } = movement else {
+    let Movement::Normal { from, to, .. } = movement else {
         return false;
     };
     if from[0] == to[0] {
@@ -47,7 +50,7 @@ fn is_valid_movement_for_rook(movement: &Movement, game_state: &GameState) -> bo
 }
 
 fn is_valid_movement_for_bishop(movement: &Movement, game_state: &GameState) -> bool {
-    let Movement::Normal { from, to } = movement else {
+    let Movement::Normal { from, to, .. } = movement else {
         return false;
     };
     let dist_x: i8 = from[0] as i8 - to[0] as i8;
@@ -85,7 +88,7 @@ fn is_valid_movement_for_queen(movement: &Movement, game_state: &GameState) -> b
 }
 
 fn is_valid_movement_for_knight(movement: &Movement) -> bool {
-    let Movement::Normal { from, to } = movement else {
+    let Movement::Normal { from, to, .. } = movement else {
         return false;
     };
     match (
@@ -99,7 +102,7 @@ fn is_valid_movement_for_knight(movement: &Movement) -> bool {
 }
 
 fn is_valid_movement_for_pawn(movement: &Movement, game_state: &GameState, piece: &Piece) -> bool {
-    let Movement::Normal { from, to } = movement else {
+    let Movement::Normal { from, to, .. } = movement else {
         return false;
     };
     let dist_x = to[0] as i8 - from[0] as i8;
@@ -114,36 +117,7 @@ fn is_valid_movement_for_pawn(movement: &Movement, game_state: &GameState, piece
                 return match game_state.board[*x][*y] {
                     Some(piece2) if piece.color != piece2.color => true, // capture
                     Some(_) => false,                                    // blocked
-                    None => {
-                        let [x0, _] = from;
-
-                        let en_passant_row = match game_state.player_to_move {
-                            Color::White => 4,
-                            Color::Black => 3,
-                        };
-                        let opponent_pawn_row = match game_state.player_to_move {
-                            Color::White => 6,
-                            Color::Black => 1,
-                        };
-                        if let Some(Movement::Normal {
-                            from: last_from,
-                            to: last_to,
-                        }) = &game_state.last_move
-                        {
-                            if last_to[0] == en_passant_row
-                                && last_from[0] == opponent_pawn_row
-                                && last_to[0] == *x0
-                                && last_to[1] == *y
-                            {
-                                if let Some(captured_piece) = game_state.board[*x0][*y] {
-                                    if captured_piece.piece_type == PieceType::Pawn {
-                                        return true;
-                                    }
-                                };
-                            }
-                        };
-                        return false;
-                    }
+                    None => game_state.en_passant_square == Some(*to), // en passant
                 };
             } else {
                 return false;
@@ -172,14 +146,29 @@ fn is_valid_movement_for_pawn(movement: &Movement, game_state: &GameState, piece
 }
 
 fn is_valid_movement_for_king(movement: &Movement) -> bool {
-    let Movement::Normal { from, to } = movement else {
+    let Movement::Normal { from, to, .. } = movement else {
         return false;
     };
     (to[0] as i8 - from[0] as i8).abs() <= 1 && (to[1] as i8 - from[1] as i8).abs() <= 1
 }
 
+/// A promotion piece can only be chosen by a pawn reaching the last row.
+fn is_valid_promotion(movement: &Movement, piece: &Piece) -> bool {
+    let Movement::Normal { to, promotion, .. } = movement else {
+        return false;
+    };
+    match promotion {
+        None => true, // a pawn reaching the last row is promoted to a queen by default
+        Some(piece_type) => {
+            piece.piece_type == PieceType::Pawn
+                && (to[0] == 0 || to[0] == 7)
+                && PROMOTION_PIECE_TYPES.contains(piece_type)
+        }
+    }
+}
+
 fn is_valid_destination(movement: &Movement, game_state: &GameState, piece: &Piece) -> bool {
-    let Movement::Normal { from: _, to: [x, y] } = movement else {
+    let Movement::Normal { to: [x, y], .. } = movement else {
         return false;
     };
     match game_state.board[*x][*y] {
@@ -199,7 +188,7 @@ pub fn is_valid_movement_for_player(
     player_color: Color,
 ) -> bool {
     match movement {
-        Movement::Normal { from: _, to: _ } => {
+        Movement::Normal { .. } => {
             is_valid_normal_movement_for_player(movement, game_state, player_color)
         }
         Movement::CastleKingSide(_) => king_castle_is_valid(game_state),
@@ -212,7 +201,7 @@ pub fn is_valid_normal_movement_for_player(
     game_state: &GameState,
     player_color: Color,
 ) -> bool {
-    let Movement::Normal { from: _, to } = movement else {
+    let Movement::Normal { to, .. } = movement else {
         return false;
     };
     let piece: Piece = movement.get_piece(game_state);
@@ -228,7 +217,10 @@ pub fn is_valid_normal_movement_for_player(
         }
     };
 
-    if piece.color == player_color && is_valid_destination(&movement, game_state, &piece) {
+    if piece.color == player_color
+        && is_valid_destination(&movement, game_state, &piece)
+        && is_valid_promotion(movement, &piece)
+    {
         match piece.piece_type {
             PieceType::King => is_valid_movement_for_king(&movement),
             PieceType::Queen => is_valid_movement_for_queen(&movement, game_state),
@@ -248,39 +240,29 @@ pub fn is_in_check(game_state: &GameState, player_color: Color) -> bool {
         color: player_color,
     };
 
-    let king_positions = game_state.get_piece_positions(player_king);
-    let Some(king_position) = king_positions.first() else { // else there is no king
+    let Some(king_position) = positions(game_state.board.pieces(player_king)).next() else {
+        // there is no king
         return false;
     };
 
     // Check if any opponent's piece can attack the position
-    return square_is_threatened_by(
-        *king_position,
-        game_state,
-        player_color.get_opponent_color(),
-    );
-}
-
-fn square_is_threatened_by(position: [usize; 2], game_state: &GameState, color: Color) -> bool {
-    for position2 in game_state.get_positions_of_color(color) {
-        if is_valid_movement_for_player(
-            &Movement::Normal {
-                from: position2,
-                to: position,
-            },
-            game_state,
-            color,
-        ) {
-            return true;
-        }
-    }
-    return false;
+    return game_state.is_attacked(king_position, player_color.get_opponent_color());
 }
 
-pub fn is_valid_cmd(cmd: &Command, game_state: &GameState) -> bool {
+/// `draw_offer` is the player whose draw offer is waiting for a reply, if any.
+pub fn is_valid_cmd(cmd: &Command, game_state: &GameState, draw_offer: Option<Color>) -> bool {
     match cmd {
         Command::Resign => true,
+        Command::Timeout => true,
+        Command::ClaimDraw => get_claimable_draw(game_state).is_some(),
+        Command::OfferDraw => true,
+        // only the opponent of the player who offered can reply
+        Command::AcceptDraw | Command::DeclineDraw => {
+            draw_offer == Some(game_state.player_to_move.get_opponent_color())
+        }
         Command::Save => true,
+        Command::SavePgn => true,
+        Command::ShowThreats => true,
         Command::Undo => true,
         Command::AcceptUndo => true,
         Command::Move(movement) => is_valid_movement(&movement, game_state),
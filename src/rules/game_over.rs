@@ -1,4 +1,5 @@
-use crate::model::{game_state::GameState, piece::Color, piece::PieceType};
+use crate::game::{GameResult, Termination};
+use crate::model::{bitboard::Bitboard, game_state::GameState, piece::Color, piece::PieceType};
 use crate::rules::cmd_validator::is_in_check;
 use crate::rules::move_generator::generate_movements;
 
@@ -38,11 +39,46 @@ fn has_insufficient_material(game_state: &GameState) -> bool {
     }
 }
 
+/// The same position occurred three times, so the player to move can claim a draw.
+pub fn is_threefold_repetition(game_state: &GameState) -> bool {
+    game_state.repetition_count() >= 3
+}
+
+/// The same position occurred five times, so the game is drawn automatically.
+pub fn is_fivefold_repetition(game_state: &GameState) -> bool {
+    game_state.repetition_count() >= 5
+}
+
+/// Fifty movements by each player without captures or pawn movements, so the player to move
+/// can claim a draw.
+pub fn is_fifty_move_rule(game_state: &GameState) -> bool {
+    game_state.halfmove_clock >= 100
+}
+
+/// Seventy-five movements by each player without captures or pawn movements, so the game is
+/// drawn automatically.
+pub fn is_seventy_five_move_rule(game_state: &GameState) -> bool {
+    game_state.halfmove_clock >= 150
+}
+
+/// Reason for which the player to move can claim a draw, if any.
+pub fn get_claimable_draw(game_state: &GameState) -> Option<Termination> {
+    if is_threefold_repetition(game_state) {
+        Some(Termination::ThreefoldRepetition)
+    } else if is_fifty_move_rule(game_state) {
+        Some(Termination::FiftyMoveRule)
+    } else {
+        None
+    }
+}
+
 pub fn is_draw(game_state: &GameState) -> bool {
-    !is_in_check(game_state, game_state.player_to_move)
-        && ((!is_in_check(game_state, game_state.player_to_move.get_opponent_color())
-            && generate_movements(game_state).len() == 0)
-            || has_insufficient_material(game_state))
+    is_fivefold_repetition(game_state)
+        || is_seventy_five_move_rule(game_state)
+        || (!is_in_check(game_state, game_state.player_to_move)
+            && ((!is_in_check(game_state, game_state.player_to_move.get_opponent_color())
+                && generate_movements(game_state).len() == 0)
+                || has_insufficient_material(game_state)))
 }
 
 #[allow(dead_code)]
@@ -56,3 +92,76 @@ pub fn is_game_over(game_state: &GameState) -> bool {
     }
     return false;
 }
+
+/// Squares of the color of h1.
+const LIGHT_SQUARES: Bitboard = 0x55AA_55AA_55AA_55AA;
+
+/// Whether some sequence of legal movements, even with the opponent's help, ends with the
+/// player checkmating the opponent.
+pub fn has_mating_material(game_state: &GameState, player_color: Color) -> bool {
+    let board = &game_state.board;
+    let pieces = board.color(player_color);
+    let opponent_pieces =
+        board.color(player_color.get_opponent_color()) & !board.piece_type(PieceType::King);
+    let heavy_pieces = board.piece_type(PieceType::Queen)
+        | board.piece_type(PieceType::Rook)
+        | board.piece_type(PieceType::Pawn);
+    if pieces & heavy_pieces != 0 {
+        return true;
+    }
+    let knights = pieces & board.piece_type(PieceType::Knight);
+    let bishops = pieces & board.piece_type(PieceType::Bishop);
+    if knights != 0 {
+        // a lone knight mates a king whose flight squares are taken by its own pieces
+        return (knights | bishops).count_ones() >= 2 || opponent_pieces != 0;
+    }
+    if bishops == 0 {
+        return false;
+    }
+    let bishop_squares = if bishops & LIGHT_SQUARES != 0 {
+        LIGHT_SQUARES
+    } else {
+        !LIGHT_SQUARES
+    };
+    if bishops & !bishop_squares != 0 {
+        return true;
+    }
+    // bishops on squares of one color never attack the flight squares of the other color, so
+    // they have to be taken by an opponent's piece that can stand there
+    opponent_pieces & !(board.piece_type(PieceType::Bishop) & bishop_squares) != 0
+}
+
+/// Result of the game when the player runs out of time: a loss, or a draw if the opponent
+/// could not checkmate.
+pub fn get_timeout_result(game_state: &GameState, player_color: Color) -> GameResult {
+    let opponent = player_color.get_opponent_color();
+    if has_mating_material(game_state, opponent) {
+        GameResult::Winner(opponent, Termination::Timeout)
+    } else {
+        GameResult::Draw(Termination::Timeout)
+    }
+}
+
+/// Result of the game if it ended on the board, without a decision of the players.
+pub fn get_game_result(game_state: &GameState) -> Option<GameResult> {
+    let player = game_state.player_to_move;
+    if is_in_check(game_state, player) {
+        if generate_movements(game_state).is_empty() {
+            return Some(GameResult::Winner(
+                player.get_opponent_color(),
+                Termination::Checkmate,
+            ));
+        }
+    } else if generate_movements(game_state).is_empty() {
+        return Some(GameResult::Draw(Termination::Stalemate));
+    }
+    if is_fivefold_repetition(game_state) {
+        Some(GameResult::Draw(Termination::FivefoldRepetition))
+    } else if is_seventy_five_move_rule(game_state) {
+        Some(GameResult::Draw(Termination::SeventyFiveMoveRule))
+    } else if has_insufficient_material(game_state) {
+        Some(GameResult::Draw(Termination::InsufficientMaterial))
+    } else {
+        None
+    }
+}
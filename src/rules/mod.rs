@@ -2,3 +2,4 @@ pub mod castle_validator;
 pub mod cmd_validator;
 pub mod game_over;
 pub mod move_generator;
+pub mod perft;
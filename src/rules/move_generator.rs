@@ -1,11 +1,40 @@
 use crate::controllers::controller::Command;
+use crate::model::attacks::{
+    bishop_attacks, king_attacks, knight_attacks, queen_attacks, rook_attacks, squares_between,
+};
+use crate::model::bitboard::{position_bit, positions, square_index, Bitboard};
 use crate::model::{
     game_state::GameState, movement::Movement, piece::Color, piece::Piece, piece::PieceType,
 };
-use crate::rules::cmd_validator::is_in_check;
 
 use super::castle_validator::{king_castle_is_valid, queen_castle_is_valid};
 
+pub const PROMOTION_PIECE_TYPES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Knight,
+    PieceType::Rook,
+    PieceType::Bishop,
+];
+
+/// Add a pawn movement, with one movement per promotion choice if it reaches the last row.
+fn push_pawn_movement(movements: &mut Vec<Movement>, from: [usize; 2], to: [usize; 2]) {
+    if to[0] == 0 || to[0] == 7 {
+        for piece_type in PROMOTION_PIECE_TYPES {
+            movements.push(Movement::Normal {
+                from,
+                to,
+                promotion: Some(piece_type),
+            });
+        }
+    } else {
+        movements.push(Movement::Normal {
+            from,
+            to,
+            promotion: None,
+        });
+    }
+}
+
 fn generate_movements_for_pawn(
     game_state: &GameState,
     x: usize,
@@ -20,10 +49,7 @@ fn generate_movements_for_pawn(
     // Normal one-step forward move
     let x2 = x as i8 + forward_offset;
     if x2 >= 0 && x2 < 8 && game_state.board[x2 as usize][y] == None {
-        movements.push(Movement::Normal {
-            from,
-            to: [x2 as usize, y],
-        });
+        push_pawn_movement(&mut movements, from, [x2 as usize, y]);
     }
 
     // Initial two-step forward move
@@ -38,6 +64,7 @@ fn generate_movements_for_pawn(
             movements.push(Movement::Normal {
                 from,
                 to: [x3 as usize, y],
+                promotion: None,
             });
         }
     }
@@ -50,97 +77,41 @@ fn generate_movements_for_pawn(
         if x4 >= 0 && x4 < 8 && y4 >= 0 && y4 < 8 {
             if let Some(piece2) = &game_state.board[x4 as usize][y4 as usize] {
                 if piece.color != piece2.color {
-                    movements.push(Movement::Normal {
-                        from,
-                        to: [x4 as usize, y4 as usize],
-                    });
+                    push_pawn_movement(&mut movements, from, [x4 as usize, y4 as usize]);
                 }
             }
         }
     }
 
     // En passant capture
-    let en_passant_row = if piece.color == Color::White { 3 } else { 4 };
-    if let Some(Movement::Normal {
-        from: last_from,
-        to: last_to,
-    }) = &game_state.last_move
-    {
-        if x == en_passant_row {
-            let left_y = y as i8 - 1;
-            let right_y = y as i8 + 1;
-
-            if left_y >= 0 {
-                if let Some(piece2) = &game_state.board[x as usize][left_y as usize] {
-                    if piece.color != piece2.color {
-                        if last_from == &[x, left_y as usize]
-                            && last_to == &[x + forward_offset as usize, y]
-                        {
-                            movements.push(Movement::Normal {
-                                from,
-                                to: [x + forward_offset as usize, left_y as usize],
-                            });
-                        }
-                    }
-                }
-            }
-
-            if right_y < 8 {
-                if let Some(piece2) = &game_state.board[x][right_y as usize] {
-                    if piece.color != piece2.color {
-                        if last_from == &[x, right_y as usize]
-                            && last_to == &[x + forward_offset as usize, y]
-                        {
-                            movements.push(Movement::Normal {
-                                from,
-                                to: [x + forward_offset as usize, right_y as usize],
-                            });
-                        }
-                    }
-                }
-            }
+    if let Some([x5, y5]) = game_state.en_passant_square {
+        if x5 as i8 == x as i8 + forward_offset && (y5 as i8 - y as i8).abs() == 1 {
+            movements.push(Movement::Normal {
+                from,
+                to: [x5, y5],
+                promotion: None,
+            });
         }
-    };
+    }
 
     movements
 }
 
-fn generate_movements_in_one_direction(
+/// Movements of the piece to the attacked squares that are not occupied by pieces of its color.
+fn generate_movements_to(
     game_state: &GameState,
     x: usize,
     y: usize,
     piece: &Piece,
-    direction: [i8; 2],
+    attacks: Bitboard,
 ) -> Vec<Movement> {
-    let source = [x, y];
-    let [mut x2, mut y2] = [x as i8, y as i8];
-    let [dx, dy] = direction;
-    let mut movements = vec![];
-
-    x2 += dx;
-    y2 += dy;
-    while x2 >= 0 && x2 < 8 && y2 >= 0 && y2 < 8 {
-        match game_state.board[x2 as usize][y2 as usize] {
-            None => {
-                movements.push(Movement::Normal {
-                    from: source,
-                    to: [x2 as usize, y2 as usize],
-                });
-            }
-            Some(piece2) => {
-                if piece.color != piece2.color {
-                    movements.push(Movement::Normal {
-                        from: source,
-                        to: [x2 as usize, y2 as usize],
-                    });
-                }
-                break;
-            }
-        }
-        x2 += dx;
-        y2 += dy;
-    }
-    movements
+    positions(attacks & !game_state.board.color(piece.color))
+        .map(|to| Movement::Normal {
+            from: [x, y],
+            to,
+            promotion: None,
+        })
+        .collect()
 }
 
 fn generate_movements_for_rook(
@@ -149,15 +120,8 @@ fn generate_movements_for_rook(
     y: usize,
     piece: &Piece,
 ) -> Vec<Movement> {
-    let mut movements = vec![];
-    let directions: [[i8; 2]; 4] = [[1, 0], [-1, 0], [0, 1], [0, -1]];
-
-    for direction in directions {
-        movements.extend(generate_movements_in_one_direction(
-            game_state, x, y, piece, direction,
-        ));
-    }
-    return movements;
+    let attacks = rook_attacks(square_index([x, y]), game_state.board.occupied());
+    generate_movements_to(game_state, x, y, piece, attacks)
 }
 
 fn generate_movements_for_bishop(
@@ -166,15 +130,8 @@ fn generate_movements_for_bishop(
     y: usize,
     piece: &Piece,
 ) -> Vec<Movement> {
-    let mut movements = vec![];
-    let directions: [[i8; 2]; 4] = [[1, 1], [-1, 1], [1, -1], [-1, -1]];
-
-    for direction in directions {
-        movements.extend(generate_movements_in_one_direction(
-            game_state, x, y, piece, direction,
-        ));
-    }
-    return movements;
+    let attacks = bishop_attacks(square_index([x, y]), game_state.board.occupied());
+    generate_movements_to(game_state, x, y, piece, attacks)
 }
 
 fn generate_movements_for_queen(
@@ -183,9 +140,8 @@ fn generate_movements_for_queen(
     y: usize,
     piece: &Piece,
 ) -> Vec<Movement> {
-    let mut movements = generate_movements_for_bishop(game_state, x, y, piece);
-    movements.extend(generate_movements_for_rook(game_state, x, y, piece));
-    return movements;
+    let attacks = queen_attacks(square_index([x, y]), game_state.board.occupied());
+    generate_movements_to(game_state, x, y, piece, attacks)
 }
 
 fn generate_movements_for_knight(
@@ -194,45 +150,8 @@ fn generate_movements_for_knight(
     y: usize,
     piece: &Piece,
 ) -> Vec<Movement> {
-    let source = [x, y];
-    let mut movements = Vec::new();
-
-    let offsets = [
-        (-2, 1),
-        (-1, 2),
-        (1, 2),
-        (2, 1),
-        (2, -1),
-        (1, -2),
-        (-1, -2),
-        (-2, -1),
-    ];
-
-    for &(dx, dy) in &offsets {
-        let x2 = x as i8 + dx;
-        let y2 = y as i8 + dy;
-
-        if x2 >= 0 && x2 < 8 && y2 >= 0 && y2 < 8 {
-            if let None = game_state.board[x2 as usize][y2 as usize] {
-                movements.push(Movement::Normal {
-                    from: source,
-                    to: [x2 as usize, y2 as usize],
-                });
-            } else {
-                // Check if the piece at the destination is of a different color
-                if let Some(piece2) = &game_state.board[x2 as usize][y2 as usize] {
-                    if piece.color != piece2.color {
-                        movements.push(Movement::Normal {
-                            from: source,
-                            to: [x2 as usize, y2 as usize],
-                        });
-                    }
-                }
-            }
-        }
-    }
-
-    movements
+    let attacks = knight_attacks(square_index([x, y]));
+    generate_movements_to(game_state, x, y, piece, attacks)
 }
 
 fn generate_movements_for_king(
@@ -241,28 +160,8 @@ fn generate_movements_for_king(
     y: usize,
     piece: &Piece,
 ) -> Vec<Movement> {
-    let mut movements = Vec::new();
-    for dx in -1..=1 {
-        for dy in -1..=1 {
-            let x2 = x as i32 + dx;
-            let y2: i32 = y as i32 + dy;
-            if 0 <= x2 && x2 < 8 && 0 <= y2 && y2 < 8 {
-                match game_state.board[x2 as usize][y2 as usize] {
-                    Some(piece2) if piece.color == piece2.color => {
-                        // position is blocked
-                        continue;
-                    }
-                    _ => {
-                        movements.push(Movement::Normal {
-                            from: [x, y],
-                            to: [x2 as usize, y2 as usize],
-                        });
-                    }
-                }
-            }
-        }
-    }
-    movements
+    let attacks = king_attacks(square_index([x, y]));
+    generate_movements_to(game_state, x, y, piece, attacks)
 }
 
 pub fn generate_movements_for_piece(
@@ -281,29 +180,125 @@ pub fn generate_movements_for_piece(
     }
 }
 
+/// Pieces of the color that can't leave the line between their king and an opponent's sliding
+/// piece, each with the squares it can move to: the line and the square of the attacker.
+fn pinned_pieces(
+    game_state: &GameState,
+    king_square: usize,
+    color: Color,
+) -> Vec<(Bitboard, Bitboard)> {
+    let board = &game_state.board;
+    let opponent = color.get_opponent_color();
+    let pieces = |piece_type| {
+        board.pieces(Piece {
+            piece_type,
+            color: opponent,
+        })
+    };
+    let queens = pieces(PieceType::Queen);
+    // sliding pieces that would attack the king if there were no pieces in between
+    let snipers = (rook_attacks(king_square, 0) & (pieces(PieceType::Rook) | queens))
+        | (bishop_attacks(king_square, 0) & (pieces(PieceType::Bishop) | queens));
+    let mut pins = vec![];
+    for sniper in positions(snipers) {
+        let line = squares_between(king_square, square_index(sniper));
+        let blockers = line & board.occupied();
+        if blockers.count_ones() == 1 && blockers & board.color(color) != 0 {
+            pins.push((blockers, line | position_bit(sniper)));
+        }
+    }
+    pins
+}
+
+/// Capturing en passant removes two pieces from the same row, which can uncover an attack
+/// on the king that the pins don't detect, so the resulting position is checked directly.
+fn is_legal_en_passant(
+    game_state: &GameState,
+    from: [usize; 2],
+    to: [usize; 2],
+    king_square: usize,
+) -> bool {
+    let board = &game_state.board;
+    let Some(pawn) = board[from[0]][from[1]] else {
+        return false;
+    };
+    let captured = position_bit([from[0], to[1]]);
+    let occupied = (board.occupied() & !position_bit(from) & !captured) | position_bit(to);
+    board.attackers(king_square, pawn.color.get_opponent_color(), occupied) & !captured == 0
+}
+
+/// Legal movements of the player. The pieces giving check and the pinned pieces are found once,
+/// and restrict the destinations of the other pieces, so the movements don't need to be tried.
 pub fn generate_movements_for_player(game_state: &GameState, color: Color) -> Vec<Movement> {
+    let board = &game_state.board;
+    let king = Piece {
+        piece_type: PieceType::King,
+        color,
+    };
+    let Some(king_position) = positions(board.pieces(king)).next() else {
+        // without king, no movement puts the player in check
+        return generate_movements_for_player_ignoring_check(game_state, color);
+    };
+    let king_square = square_index(king_position);
+    let opponent = color.get_opponent_color();
+    let checkers = board.attackers(king_square, opponent, board.occupied());
+
     let mut movements = Vec::new();
-    for x in 0..8 {
-        for y in 0..8 {
-            match game_state.board[x][y] {
-                Some(piece) if piece.color == color => {
-                    for movement in generate_movements_for_piece(game_state, x, y, piece) {
-                        let game_state2 = game_state.clone_and_move(movement.clone());
-                        if !is_in_check(&game_state2, color) {
-                            // Player can't put himself in check
-                            movements.push(movement);
-                        }
-                    }
-                }
-                _ => continue,
-            }
+    // the king can't stay on the line of a sliding piece that gives check by moving away from it
+    let occupied_without_king = board.occupied() & !position_bit(king_position);
+    let [x, y] = king_position;
+    for movement in generate_movements_for_piece(game_state, x, y, king) {
+        let Movement::Normal { to, .. } = movement else {
+            continue;
+        };
+        if board.attackers(square_index(to), opponent, occupied_without_king) == 0 {
+            movements.push(movement);
         }
     }
-    if king_castle_is_valid(game_state) {
-        movements.push(Movement::CastleKingSide(color));
+    if checkers.count_ones() > 1 {
+        // double check: only the king can move
+        return movements;
     }
-    if queen_castle_is_valid(game_state) {
-        movements.push(Movement::CastleQueenSide(color));
+
+    // in check, the other pieces can only capture the attacker or block its line
+    let targets = match positions(checkers).next() {
+        Some(checker) => {
+            position_bit(checker) | squares_between(king_square, square_index(checker))
+        }
+        None => !0,
+    };
+    let pins = pinned_pieces(game_state, king_square, color);
+    for [x, y] in positions(board.color(color) & !board.pieces(king)) {
+        let piece = board[x][y].unwrap();
+        let pin_line = pins
+            .iter()
+            .find(|(pinned, _)| pinned & position_bit([x, y]) != 0)
+            .map_or(!0, |(_, line)| *line);
+        for movement in generate_movements_for_piece(game_state, x, y, piece) {
+            let Movement::Normal { to, .. } = movement else {
+                continue;
+            };
+            let is_en_passant = piece.piece_type == PieceType::Pawn
+                && game_state.en_passant_square == Some(to)
+                && y != to[1];
+            let is_legal = if is_en_passant {
+                is_legal_en_passant(game_state, [x, y], to, king_square)
+            } else {
+                targets & pin_line & position_bit(to) != 0
+            };
+            if is_legal {
+                movements.push(movement);
+            }
+        }
+    }
+
+    if checkers == 0 {
+        if king_castle_is_valid(game_state) {
+            movements.push(Movement::CastleKingSide(color));
+        }
+        if queen_castle_is_valid(game_state) {
+            movements.push(Movement::CastleQueenSide(color));
+        }
     }
     movements
 }
@@ -325,15 +320,9 @@ pub fn generate_movements_for_player_ignoring_check(
     color: Color,
 ) -> Vec<Movement> {
     let mut movements = Vec::new();
-    for x in 0..8 {
-        for y in 0..8 {
-            match game_state.board[x][y] {
-                Some(piece) if piece.color == color => {
-                    movements.extend(generate_movements_for_piece(game_state, x, y, piece));
-                }
-                _ => continue,
-            }
-        }
+    for [x, y] in positions(game_state.board.color(color)) {
+        let piece = game_state.board[x][y].unwrap();
+        movements.extend(generate_movements_for_piece(game_state, x, y, piece));
     }
     if king_castle_is_valid(game_state) {
         movements.push(Movement::CastleKingSide(color));
@@ -0,0 +1,38 @@
+//! Performance test (perft): count the leaves of the tree of legal movements to a given depth,
+//! to compare the move generator with the published node counts of known positions.
+
+use crate::model::{game_state::GameState, movement::Movement};
+use crate::rules::move_generator::generate_movements;
+
+/// Number of sequences of legal movements of the given length from the position.
+pub fn perft(game_state: &mut GameState, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let movements = generate_movements(game_state);
+    if depth == 1 {
+        // the leaves don't need to be played
+        return movements.len() as u64;
+    }
+    let mut nodes = 0;
+    for movement in movements {
+        let undo = game_state.make_move(movement);
+        nodes += perft(game_state, depth - 1);
+        game_state.unmake_move(undo);
+    }
+    nodes
+}
+
+/// Perft split by the first movement, to find which movement leads to a wrong count.
+pub fn divide(game_state: &mut GameState, depth: u32) -> Vec<(Movement, u64)> {
+    let mut counts = vec![];
+    if depth == 0 {
+        return counts;
+    }
+    for movement in generate_movements(game_state) {
+        let undo = game_state.make_move(movement.clone());
+        counts.push((movement, perft(game_state, depth - 1)));
+        game_state.unmake_move(undo);
+    }
+    counts
+}
@@ -1,6 +1,9 @@
 use crate::{
-    model::{game_state::GameState, piece::Color, piece::Piece},
-    rules::game_over::{is_draw, is_in_check_mate},
+    game::{GameResult, Termination},
+    model::{
+        bitboard::positions, board::position_to_string, clock::Clock, game_state::GameState,
+        movement::Movement, piece::Color, piece::Piece,
+    },
 };
 
 pub trait GameDisplay {
@@ -61,16 +64,45 @@ pub trait GameDisplay {
     }
     fn piece_to_char(&self, piece: &Piece) -> char;
 
-    fn display_game_over(&self, game_state: &GameState) {
-        if is_in_check_mate(game_state, game_state.player_to_move) {
+    fn display_clock(&self, clock: &Clock) {
+        println!("{}", clock);
+    }
+
+    /// Show the pieces of the player to move that the opponent attacks, with the number of
+    /// attackers and defenders of each square, x-rays included.
+    fn display_threats(&self, game_state: &GameState) {
+        let player = game_state.player_to_move;
+        let opponent = player.get_opponent_color();
+        let threatened = game_state.threatened_pieces(player);
+        if threatened == 0 {
+            println!("No pieces are threatened");
+        }
+        for position in positions(threatened) {
+            let [x, y] = position;
+            println!(
+                "{} {}: {} attackers, {} defenders",
+                self.piece_to_char(&game_state.board[x][y].unwrap()),
+                position_to_string(position),
+                game_state.xray_attack_count(position, opponent),
+                game_state.xray_attack_count(position, player)
+            );
+        }
+    }
+
+    /// Show a movement that is about to be made on the game state.
+    fn display_movement(&self, movement: &Movement, game_state: &GameState) {
+        let san = movement.to_san(game_state);
+        match game_state.player_to_move {
+            Color::White => println!("{}. {}", game_state.fullmove_number, san),
+            Color::Black => println!("{}... {}", game_state.fullmove_number, san),
+        }
+    }
+
+    fn display_game_over(&self, result: &GameResult) {
+        if result.termination() == Termination::Checkmate {
             println!("Check mate!");
-            match game_state.player_to_move {
-                Color::White => println!("Black wins"),
-                Color::Black => println!("White wins"),
-            }
-        } else if is_draw(game_state) {
-            println!("Draw!");
         }
+        println!("{}", result);
     }
 }
 
@@ -93,10 +125,15 @@ impl GameDisplay for NoDisplay {
     fn display_game(&self, _game_state: &GameState) {
         return;
     }
+    fn display_clock(&self, _clock: &Clock) {}
+    fn display_threats(&self, _game_state: &GameState) {}
     fn piece_to_char(&self, _piece: &Piece) -> char {
         return ' ';
     }
-    fn display_game_over(&self, _game_state: &GameState) {
+    fn display_movement(&self, _movement: &Movement, _game_state: &GameState) {
+        return;
+    }
+    fn display_game_over(&self, _result: &GameResult) {
         return;
     }
 }
@@ -0,0 +1,30 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use rust_chess::controllers::controller::{Command, Controller};
+use rust_chess::model::{clock::Clock, game_state::GameState, movement::Movement};
+
+/// Controller that plays a fixed sequence of commands.
+pub struct ScriptedController {
+    pub commands: Vec<Command>,
+}
+
+impl Controller for ScriptedController {
+    fn choose_command(&mut self, _game_state: &mut GameState, _clock: Option<&Clock>) -> Command {
+        self.commands.remove(0)
+    }
+}
+
+pub fn normal(from: [usize; 2], to: [usize; 2]) -> Movement {
+    Movement::Normal {
+        from,
+        to,
+        promotion: None,
+    }
+}
+
+pub fn seconds(n: u64) -> Duration {
+    Duration::from_secs(n)
+}
@@ -0,0 +1,271 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rust_chess::model::attacks::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+};
+use rust_chess::model::bitboard::{position_bit, positions, square_index, Bitboard, Bitboards};
+use rust_chess::model::{
+    game_state::GameState,
+    piece::{Color, Piece, PieceType},
+};
+use rust_chess::rules::game_over::is_game_over;
+use rust_chess::rules::move_generator::generate_movements;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitboard(squares: &[[usize; 2]]) -> Bitboard {
+        squares.iter().map(|&position| position_bit(position)).sum()
+    }
+
+    /// Attacks found by walking the rays square by square.
+    fn slow_sliding_attacks(
+        position: [usize; 2],
+        occupied: Bitboard,
+        directions: &[(i32, i32)],
+    ) -> Bitboard {
+        let mut attacks = 0;
+        for (dx, dy) in directions {
+            let [mut x, mut y] = [position[0] as i32 + dx, position[1] as i32 + dy];
+            while (0..8).contains(&x) && (0..8).contains(&y) {
+                let bit = position_bit([x as usize, y as usize]);
+                attacks |= bit;
+                if occupied & bit != 0 {
+                    break;
+                }
+                x += dx;
+                y += dy;
+            }
+        }
+        attacks
+    }
+
+    #[test]
+    fn test_leaper_attacks() {
+        assert_eq!(
+            knight_attacks(square_index([0, 0])),
+            bitboard(&[[1, 2], [2, 1]])
+        );
+        assert_eq!(knight_attacks(square_index([3, 3])).count_ones(), 8);
+        assert_eq!(
+            king_attacks(square_index([0, 7])),
+            bitboard(&[[0, 6], [1, 6], [1, 7]])
+        );
+        assert_eq!(
+            pawn_attacks(Color::White, square_index([1, 0])),
+            bitboard(&[[2, 1]])
+        );
+        assert_eq!(
+            pawn_attacks(Color::Black, square_index([6, 4])),
+            bitboard(&[[5, 3], [5, 5]])
+        );
+    }
+
+    #[test]
+    fn test_sliding_attacks() {
+        // empty board
+        assert_eq!(rook_attacks(square_index([0, 0]), 0).count_ones(), 14);
+        assert_eq!(bishop_attacks(square_index([3, 3]), 0).count_ones(), 13);
+        assert_eq!(queen_attacks(square_index([3, 3]), 0).count_ones(), 27);
+        // the first blocker is attacked, the squares behind it are not
+        let occupied = bitboard(&[[3, 0], [3, 5], [5, 3]]);
+        assert_eq!(
+            rook_attacks(square_index([3, 3]), occupied),
+            bitboard(&[
+                [3, 0],
+                [3, 1],
+                [3, 2],
+                [3, 4],
+                [3, 5],
+                [4, 3],
+                [5, 3],
+                [2, 3],
+                [1, 3],
+                [0, 3]
+            ])
+        );
+
+        let rook_directions = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        let bishop_directions = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let occupied: Bitboard = rng.gen::<u64>() & rng.gen::<u64>();
+            let square = rng.gen_range(0, 64);
+            let position = [square / 8, square % 8];
+            assert_eq!(
+                rook_attacks(square, occupied),
+                slow_sliding_attacks(position, occupied, &rook_directions)
+            );
+            assert_eq!(
+                bishop_attacks(square, occupied),
+                slow_sliding_attacks(position, occupied, &bishop_directions)
+            );
+        }
+    }
+
+    /// The bitboards have the same pieces as the array of squares.
+    fn assert_bitboards_match_squares(game_state: &GameState) {
+        for color in [Color::White, Color::Black] {
+            for piece_type in [
+                PieceType::King,
+                PieceType::Queen,
+                PieceType::Rook,
+                PieceType::Bishop,
+                PieceType::Knight,
+                PieceType::Pawn,
+            ] {
+                let piece = Piece { piece_type, color };
+                let mut expected = 0;
+                for x in 0..8 {
+                    for y in 0..8 {
+                        if game_state.board[x][y] == Some(piece) {
+                            expected |= position_bit([x, y]);
+                        }
+                    }
+                }
+                assert_eq!(game_state.board.pieces(piece), expected, "{:?}", piece);
+            }
+        }
+        assert_eq!(
+            game_state.board.occupied(),
+            game_state.board.color(Color::White) | game_state.board.color(Color::Black)
+        );
+        // the hash updated with each change is the hash of the whole board
+        assert_eq!(
+            game_state.board.hash(),
+            Bitboards::new(*game_state.board.squares()).hash()
+        );
+    }
+
+    #[test]
+    fn test_bitboards_follow_movements() {
+        let game_state = GameState::new();
+        assert_eq!(game_state.board.occupied().count_ones(), 32);
+        assert_eq!(
+            positions(game_state.board.pieces(Piece {
+                piece_type: PieceType::King,
+                color: Color::Black
+            }))
+            .collect::<Vec<_>>(),
+            vec![[7, 4]]
+        );
+
+        // random games cover captures, castling, en passant and promotions
+        let mut rng = rand::thread_rng();
+        for mut game_state in [GameState::new(), GameState::new960()] {
+            for _ in 0..200 {
+                assert_bitboards_match_squares(&game_state);
+                if is_game_over(&game_state) {
+                    break;
+                }
+                let movements = generate_movements(&game_state);
+                let movement = movements.choose(&mut rng).unwrap().clone();
+                game_state.make_movement(movement);
+            }
+        }
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut game_state = GameState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        game_state.board.take([0, 0]);
+        let json = serde_json::to_string(&game_state).unwrap();
+        // the board is saved as an array of squares
+        assert!(json.contains(r#""board":[[null,null,null,null,"K",null,null,"R"]"#));
+        let loaded_state: GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded_state, game_state);
+        assert_bitboards_match_squares(&loaded_state);
+    }
+
+    #[test]
+    fn test_attack_queries() {
+        let game_state = GameState::from_fen("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+        let d5 = [4, 3];
+        assert_eq!(game_state.attackers(d5, Color::White), bitboard(&[[1, 3]]));
+        assert_eq!(game_state.attack_count(d5, Color::White), 1);
+        assert_eq!(game_state.attack_count(d5, Color::Black), 1);
+        // the rook on d1 attacks d5 once the rook on d2 has captured
+        assert_eq!(
+            game_state.xray_attackers(d5, Color::White),
+            bitboard(&[[0, 3], [1, 3]])
+        );
+        let occupied = game_state.board.occupied() & !bitboard(&[[1, 3]]);
+        assert_eq!(
+            game_state.attackers_with_occupancy(d5, Color::White, occupied),
+            bitboard(&[[0, 3]])
+        );
+        assert_eq!(game_state.xray_attack_count(d5, Color::Black), 1);
+        assert!(!game_state.is_attacked([2, 0], Color::Black));
+        assert_eq!(
+            game_state.threatened_pieces(Color::Black),
+            bitboard(&[[4, 3]])
+        );
+
+        // a bishop behind a pawn, and a queen behind a rook
+        let game_state = GameState::from_fen("3qk3/8/3r4/3p4/4P3/5B2/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(game_state.attackers(d5, Color::White), bitboard(&[[3, 4]]));
+        assert_eq!(
+            game_state.xray_attackers(d5, Color::White),
+            bitboard(&[[3, 4], [2, 5]])
+        );
+        assert_eq!(
+            game_state.xray_attackers(d5, Color::Black),
+            bitboard(&[[5, 3], [7, 3]])
+        );
+        assert_eq!(game_state.attacks_from([5, 5]), 0);
+        assert_eq!(game_state.attacks_from([3, 4]), bitboard(&[[4, 3], [4, 5]]));
+    }
+
+    #[test]
+    fn test_attack_map() {
+        let mut rng = rand::thread_rng();
+        for mut game_state in [GameState::new(), GameState::new960()] {
+            for _ in 0..100 {
+                let attack_map = game_state.attack_map();
+                for color in [Color::White, Color::Black] {
+                    let mut attacked = 0;
+                    for position in positions(!0) {
+                        // the pieces whose attacks include the square
+                        let attackers: Bitboard = positions(game_state.board.color(color))
+                            .filter(|&from| {
+                                game_state.attacks_from(from) & position_bit(position) != 0
+                            })
+                            .map(position_bit)
+                            .sum();
+                        assert_eq!(game_state.attackers(position, color), attackers);
+                        assert_eq!(
+                            attack_map.attack_count(position, color),
+                            attackers.count_ones()
+                        );
+                        assert_eq!(
+                            attack_map.is_attacked(position, color),
+                            game_state.is_attacked(position, color)
+                        );
+                        assert_eq!(
+                            game_state.xray_attackers(position, color) & attackers,
+                            attackers
+                        );
+                        assert_eq!(
+                            attack_map.attacks_from(position),
+                            game_state.attacks_from(position)
+                        );
+                        if attackers != 0 {
+                            attacked |= position_bit(position);
+                        }
+                    }
+                    assert_eq!(attack_map.attacked_squares(color), attacked);
+                    assert_eq!(game_state.attacked_squares(color), attacked);
+                }
+                if is_game_over(&game_state) {
+                    break;
+                }
+                let movements = generate_movements(&game_state);
+                game_state.make_movement(movements.choose(&mut rng).unwrap().clone());
+            }
+        }
+    }
+}
@@ -20,27 +20,25 @@ mod tests {
 
     fn is_valid_position(game_state: &GameState) -> bool {
         let mut king_count = 0;
-        let mut white_rook_count = 0;
-        let mut black_rook_count = 0;
-        for row in game_state.board {
+        // rooks and pawns per color: a promoted rook replaces one of the pawns
+        let mut rooks_and_pawns = [0, 0];
+        for row in *game_state.board {
             for square in row {
                 match square {
                     Some(piece) if piece.piece_type == PieceType::King => {
                         king_count += 1;
                     }
-                    Some(piece) if piece.piece_type == PieceType::Rook => match piece.color {
-                        Color::White => {
-                            white_rook_count += 1;
-                        }
-                        Color::Black => {
-                            black_rook_count += 1;
-                        }
-                    },
+                    Some(piece)
+                        if piece.piece_type == PieceType::Rook
+                            || piece.piece_type == PieceType::Pawn =>
+                    {
+                        rooks_and_pawns[piece.color as usize] += 1;
+                    }
                     _ => {}
                 }
             }
         }
-        return king_count == 2 && white_rook_count <= 2 && black_rook_count <= 2;
+        return king_count == 2 && rooks_and_pawns[0] <= 10 && rooks_and_pawns[1] <= 10;
     }
 
     #[test]
@@ -95,13 +93,13 @@ mod tests {
 
             let result = game.play();
             match result {
-                GameResult::Winner(Color::White) => {
+                GameResult::Winner(Color::White, _) => {
                     n_victories += 1;
                 }
-                GameResult::Draw => {
+                GameResult::Draw(_) => {
                     n_draws += 1;
                 }
-                GameResult::Winner(Color::Black) => {
+                GameResult::Winner(Color::Black, _) => {
                     n_defeats += 1;
                 }
             };
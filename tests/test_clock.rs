@@ -0,0 +1,264 @@
+mod common;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use common::{normal, seconds, ScriptedController};
+use rust_chess::controllers::controller::{Command, Controller};
+use rust_chess::game::{load_game_from_json, write_game_to_json, Game, GameResult, Termination};
+use rust_chess::model::clock::{format_duration, Clock, TimeControl};
+use rust_chess::model::game_state::{write_game_state_to_json, GameState};
+use rust_chess::model::{movement::Movement, piece::Color};
+use rust_chess::notation::pgn::write_pgn;
+use rust_chess::rules::game_over::get_timeout_result;
+use rust_chess::view::NoDisplay;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Controller that takes its time to choose each command, and notes the time it had left.
+    struct SlowController {
+        commands: Vec<Command>,
+        thinking_time: Duration,
+        remaining: Rc<RefCell<Vec<Duration>>>,
+    }
+
+    impl Controller for SlowController {
+        fn choose_command(&mut self, game_state: &mut GameState, clock: Option<&Clock>) -> Command {
+            let remaining = clock.unwrap().get_remaining(game_state.player_to_move);
+            self.remaining.borrow_mut().push(remaining);
+            thread::sleep(self.thinking_time);
+            self.commands.remove(0)
+        }
+    }
+
+    #[test]
+    fn test_time_controls() {
+        let mut clock = Clock::new(TimeControl::SuddenDeath { time: seconds(60) });
+        clock.record_movement(Color::White, seconds(10));
+        assert_eq!(clock.get_remaining(Color::White), seconds(50));
+        assert_eq!(clock.get_remaining(Color::Black), seconds(60));
+        assert!(clock.is_out_of_time(Color::White, seconds(50)));
+        assert!(!clock.is_out_of_time(Color::Black, seconds(50)));
+
+        let mut clock = Clock::new(TimeControl::Increment {
+            time: seconds(60),
+            increment: seconds(2),
+        });
+        clock.record_movement(Color::Black, seconds(10));
+        assert_eq!(clock.get_remaining(Color::Black), seconds(52));
+
+        let mut clock = Clock::new(TimeControl::Delay {
+            time: seconds(60),
+            delay: seconds(5),
+        });
+        clock.record_movement(Color::White, seconds(3));
+        assert_eq!(clock.get_remaining(Color::White), seconds(60));
+        clock.record_movement(Color::White, seconds(10));
+        assert_eq!(clock.get_remaining(Color::White), seconds(55));
+
+        let mut clock = Clock::new(TimeControl::MovesPerPeriod {
+            time: seconds(60),
+            moves: 2,
+        });
+        clock.record_movement(Color::White, seconds(10));
+        assert_eq!(clock.get_remaining(Color::White), seconds(50));
+        clock.record_movement(Color::White, seconds(10));
+        assert_eq!(clock.get_remaining(Color::White), seconds(100));
+        clock.record_movement(Color::White, seconds(10));
+        assert_eq!(clock.get_remaining(Color::White), seconds(90));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(seconds(5400)), "1:30:00");
+        assert_eq!(format_duration(seconds(300)), "5:00");
+        assert_eq!(format_duration(seconds(61)), "1:01");
+        assert_eq!(format_duration(Duration::from_millis(9750)), "0:09.7");
+        let clock = Clock::new(TimeControl::SuddenDeath { time: seconds(180) });
+        assert_eq!(clock.to_string(), "White 3:00 | Black 3:00");
+    }
+
+    fn game_on_time(fen: &str, time_control: TimeControl) -> Game {
+        let game_state = GameState::from_fen(fen).unwrap();
+        let movement = Movement::Normal {
+            from: [0, 4],
+            to: [1, 4],
+            promotion: None,
+        };
+        let mut game = Game::new(
+            game_state,
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![Command::Move(movement)],
+                }),
+                Box::new(ScriptedController { commands: vec![] }),
+            ],
+        );
+        game.clock = Some(Clock::new(time_control));
+        game
+    }
+
+    #[test]
+    fn test_timeout() {
+        let no_time = TimeControl::SuddenDeath {
+            time: Duration::ZERO,
+        };
+        let mut game = game_on_time("3qk3/8/8/8/8/8/8/4K3 w - - 0 1", no_time);
+        assert_eq!(
+            game.play(),
+            GameResult::Winner(Color::Black, Termination::Timeout)
+        );
+        assert!(game.history.is_empty());
+
+        // the opponent can't checkmate with a lone knight
+        let mut game = game_on_time("3nk3/8/8/8/8/8/8/4K3 w - - 0 1", no_time);
+        assert_eq!(game.play(), GameResult::Draw(Termination::Timeout));
+
+        // the flag fell on the clock of a remote opponent
+        let mut game = game_on_time(
+            "3qk3/8/8/8/8/8/8/4K3 w - - 0 1",
+            TimeControl::SuddenDeath { time: seconds(60) },
+        );
+        game.execute_command(Command::Timeout);
+        assert_eq!(
+            game.result,
+            Some(GameResult::Winner(Color::Black, Termination::Timeout))
+        );
+        let clock = game.clock.clone().unwrap();
+        assert_eq!(clock.get_remaining(Color::White), Duration::ZERO);
+        // without a clock nobody runs out of time
+        game.result = None;
+        game.clock = None;
+        game.execute_command(Command::Timeout);
+        assert_eq!(game.result, None);
+    }
+
+    #[test]
+    fn test_flag_falls_while_thinking() {
+        let remaining = Rc::new(RefCell::new(vec![]));
+        let mut game = Game::new(
+            GameState::new(),
+            Box::new(NoDisplay),
+            [
+                Box::new(SlowController {
+                    commands: vec![Command::ShowThreats, Command::Move(normal([1, 4], [3, 4]))],
+                    thinking_time: Duration::from_millis(30),
+                    remaining: remaining.clone(),
+                }),
+                Box::new(ScriptedController { commands: vec![] }),
+            ],
+        );
+        game.clock = Some(Clock::new(TimeControl::SuddenDeath {
+            time: Duration::from_millis(50),
+        }));
+        // the movement comes too late
+        assert_eq!(
+            game.play(),
+            GameResult::Winner(Color::Black, Termination::Timeout)
+        );
+        assert!(game.history.is_empty());
+        // the time spent on the first command is discounted from the clock of the second one
+        let remaining = remaining.borrow();
+        assert!(remaining[0] > Duration::from_millis(40));
+        assert!(remaining[1] <= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_mating_material() {
+        let mating_positions = [
+            // a lone knight or bishop mates a king blocked by its own pieces
+            "3nk3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "3bk3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "3nk3/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "3nnk2/8/8/8/8/8/8/4K3 w - - 0 1",
+            "2b1kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            "3bk3/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "3bk3/8/8/8/8/8/8/3NK3 w - - 0 1",
+        ];
+        for fen in mating_positions {
+            let game_state = GameState::from_fen(fen).unwrap();
+            assert_eq!(
+                get_timeout_result(&game_state, Color::White),
+                GameResult::Winner(Color::Black, Termination::Timeout),
+                "{}",
+                fen
+            );
+        }
+        let dead_positions = [
+            "3bk3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            // bishops on squares of the same color can't attack the other flight squares
+            "3bkb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            "3bkb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ];
+        for fen in dead_positions {
+            let game_state = GameState::from_fen(fen).unwrap();
+            assert_eq!(
+                get_timeout_result(&game_state, Color::White),
+                GameResult::Draw(Termination::Timeout),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_undo_gives_time_back() {
+        let time_control = TimeControl::MovesPerPeriod {
+            time: seconds(60),
+            moves: 1,
+        };
+        let mut game = Game::new(
+            GameState::new(),
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![Command::Move(normal([1, 4], [3, 4])), Command::Undo],
+                }),
+                Box::new(ScriptedController {
+                    commands: vec![Command::Move(normal([6, 4], [4, 4]))],
+                }),
+            ],
+        );
+        game.clock = Some(Clock::new(time_control));
+        for _ in 0..3 {
+            game.player_turn();
+        }
+        assert!(game.history.is_empty());
+        assert_eq!(game.clock, Some(Clock::new(time_control)));
+    }
+
+    #[test]
+    fn test_clock_is_saved() {
+        let mut game = game_on_time(
+            "3qk3/8/8/8/8/8/8/4K3 w - - 0 1",
+            TimeControl::Increment {
+                time: seconds(300),
+                increment: seconds(3),
+            },
+        );
+        game.player_turn();
+        let clock = game.clock.clone().unwrap();
+        assert!(clock.get_remaining(Color::White) > seconds(300));
+        assert_eq!(clock.get_remaining(Color::Black), seconds(300));
+
+        let file_path = env::temp_dir().join("rust_chess_test_clock.json");
+        let file_path = file_path.to_str().unwrap();
+        write_game_to_json(&game, file_path).unwrap();
+        let saved_game = load_game_from_json(file_path).unwrap();
+        assert_eq!(saved_game.clock, Some(clock));
+        assert_eq!(saved_game.game_state, game.game_state);
+        // files saved without a clock are still read
+        write_game_state_to_json(&game.game_state, file_path).unwrap();
+        assert_eq!(load_game_from_json(file_path).unwrap().clock, None);
+        fs::remove_file(file_path).unwrap();
+        assert!(write_pgn(&game).contains("[TimeControl \"300+3\"]"));
+    }
+}
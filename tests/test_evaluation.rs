@@ -0,0 +1,236 @@
+use rust_chess::controllers::alphabeta::AlphaBetaBot;
+use rust_chess::controllers::controller::{Command, Controller};
+use rust_chess::controllers::minimax::MinimaxBot;
+use rust_chess::evaluation::king_safety::evaluate_king_safety;
+use rust_chess::evaluation::pawn_structure::evaluate_pawn_structure;
+use rust_chess::evaluation::piece_activity::evaluate_piece_activity;
+use rust_chess::evaluation::{
+    evaluate_full, evaluate_material, evaluate_positions, Evaluator, MaterialEvaluator,
+};
+use rust_chess::model::movement::Movement;
+use rust_chess::model::{game_state::GameState, piece::Color, piece::PieceType};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate_fen(fen: &str, player_color: Color) -> i32 {
+        evaluate_positions(&GameState::from_fen(fen).unwrap(), player_color)
+    }
+
+    #[test]
+    fn test_evaluation_is_symmetric() {
+        let game_state = GameState::new();
+        assert_eq!(evaluate_positions(&game_state, Color::White), 0);
+        assert_eq!(evaluate_positions(&game_state, Color::Black), 0);
+
+        // the same position with the colors swapped
+        let white = evaluate_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            Color::White,
+        );
+        let black = evaluate_fen(
+            "rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3",
+            Color::Black,
+        );
+        assert_eq!(white, black);
+        assert_eq!(
+            evaluate_fen(
+                "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+                Color::Black
+            ),
+            -white
+        );
+    }
+
+    #[test]
+    fn test_piece_square_tables() {
+        // a knight in the center is better than on the rim
+        let center = evaluate_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", Color::White);
+        let rim = evaluate_fen("4k3/8/8/8/N7/8/8/4K3 w - - 0 1", Color::White);
+        assert!(center > rim);
+
+        // advanced pawns are worth more
+        let advanced = evaluate_fen("4k3/8/4P3/8/8/8/8/4K3 w - - 0 1", Color::White);
+        let initial = evaluate_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", Color::White);
+        assert!(advanced > initial);
+    }
+
+    #[test]
+    fn test_tapered_evaluation() {
+        // the king should stay sheltered in the middlegame but be active in the endgame
+        let middlegame_sheltered =
+            evaluate_fen("rnbqkbnr/8/8/8/8/8/8/RNBQ1RK1 w kq - 0 1", Color::White);
+        let middlegame_active =
+            evaluate_fen("rnbqkbnr/8/8/8/4K3/8/8/RNBQ1R2 w kq - 0 1", Color::White);
+        assert!(middlegame_sheltered > middlegame_active);
+
+        let endgame_sheltered = evaluate_fen("4k3/8/8/8/8/8/8/5RK1 w - - 0 1", Color::White);
+        let endgame_active = evaluate_fen("4k3/8/8/8/4K3/8/8/5R2 w - - 0 1", Color::White);
+        assert!(endgame_active > endgame_sheltered);
+    }
+
+    #[test]
+    fn test_evaluation_per_bot() {
+        let game_state = GameState::new();
+        assert_eq!(evaluate_material(&game_state, Color::White), 0);
+        let mut bot = AlphaBetaBot::new(2);
+        bot.set_evaluator(MaterialEvaluator);
+        let Command::Move(_) = bot.choose_command(&mut game_state.clone(), None) else {
+            panic!("The bot didn't move");
+        };
+    }
+
+    fn pawn_structure(fen: &str) -> [i32; 2] {
+        evaluate_pawn_structure(&GameState::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_pawn_structure() {
+        // the same pawns for both players
+        assert_eq!(
+            pawn_structure("4k3/pp3ppp/8/8/8/8/PP3PPP/4K3 w - - 0 1"),
+            [0, 0]
+        );
+        // doubled and isolated pawns
+        let healthy = pawn_structure("4k3/ppp5/8/8/8/8/PPP5/4K3 w - - 0 1");
+        let doubled = pawn_structure("4k3/ppp5/8/8/8/P7/P1P5/4K3 w - - 0 1");
+        assert!(doubled[0] < healthy[0] && doubled[1] < healthy[1]);
+        // a passed pawn is worth more when it is advanced and in the endgame
+        let passed = pawn_structure("4k3/8/8/8/8/P7/8/4K3 w - - 0 1");
+        let advanced_passed = pawn_structure("4k3/8/P7/8/8/8/8/4K3 w - - 0 1");
+        assert!(advanced_passed[0] > passed[0]);
+        assert!(advanced_passed[1] - passed[1] > advanced_passed[0] - passed[0]);
+        // opponent pawns ahead on the same or an adjacent file stop both pawns
+        assert_eq!(pawn_structure("4k3/p7/8/8/8/P7/8/4K3 w - - 0 1"), [0, 0]);
+        assert_eq!(pawn_structure("4k3/1p6/8/8/8/P7/8/4K3 w - - 0 1"), [0, 0]);
+        // pawns that have passed each other are both passed
+        let white_passed = pawn_structure("4k3/8/8/P7/8/8/8/4K3 w - - 0 1");
+        let black_passed = pawn_structure("4k3/8/8/8/8/1p6/8/4K3 w - - 0 1");
+        assert_eq!(
+            pawn_structure("4k3/8/8/P7/8/1p6/8/4K3 w - - 0 1"),
+            [
+                white_passed[0] + black_passed[0],
+                white_passed[1] + black_passed[1]
+            ]
+        );
+        // the d-pawn is backward: its neighbours are ahead and d4 is attacked by the c-pawn
+        let backward = pawn_structure("4k3/8/8/2p5/2P1P3/3P4/8/4K3 w - - 0 1");
+        let supported = pawn_structure("4k3/8/8/2p5/2PPP3/8/8/4K3 w - - 0 1");
+        assert!(backward[0] < supported[0]);
+
+        // cached scores are the same as computed ones
+        let game_state = GameState::from_fen("4k3/8/8/2p5/2P1P3/3P4/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate_pawn_structure(&game_state), backward);
+        let mut moved_king = game_state.clone();
+        let king = moved_king.board.take([0, 4]);
+        moved_king.board.set([0, 5], king);
+        assert_eq!(moved_king.pawn_hash(), game_state.pawn_hash());
+        assert_eq!(evaluate_pawn_structure(&moved_king), backward);
+        // the pawn hash follows the movements of the pawns
+        let mut moved_pawn = game_state.clone();
+        let pawn = moved_pawn.board.take([2, 3]);
+        moved_pawn.board.set([3, 3], pawn);
+        let supported_state = GameState::from_fen("4k3/8/8/2p5/2PPP3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(moved_pawn.pawn_hash(), supported_state.pawn_hash());
+        assert_ne!(moved_pawn.pawn_hash(), game_state.pawn_hash());
+    }
+
+    #[test]
+    fn test_king_safety() {
+        let sheltered = GameState::from_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        assert_eq!(evaluate_king_safety(&sheltered.board), [0, 0]);
+        let exposed = GameState::from_fen("6k1/5ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        let score = evaluate_king_safety(&exposed.board);
+        assert!(score[0] < 0);
+        // only the middlegame is affected
+        assert_eq!(score[1], 0);
+    }
+
+    #[test]
+    fn test_piece_activity() {
+        let activity =
+            |fen: &str| evaluate_piece_activity(&GameState::from_fen(fen).unwrap());
+        assert_eq!(activity("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), [0, 0]);
+        // a centralized bishop attacks more squares
+        let center = activity("4k3/8/8/8/3B4/8/8/4K3 w - - 0 1");
+        let corner = activity("4k3/8/8/8/8/8/8/B3K3 w - - 0 1");
+        assert!(center[0] > corner[0]);
+        // bishop pair
+        let pair = activity("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1");
+        let single = activity("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1");
+        assert!(pair[1] > single[1]);
+        // rook on an open file
+        let open_file = activity("4k3/p7/8/8/8/8/P7/4KR2 w - - 0 1");
+        let closed_file = activity("4k3/p7/8/8/8/8/P7/R3K3 w - - 0 1");
+        assert!(open_file[0] > closed_file[0]);
+        // knight outpost on d5, protected by the e4 pawn
+        let outpost = activity("4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1");
+        let attackable = activity("4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1");
+        assert!(outpost[0] > attackable[0]);
+    }
+
+    #[test]
+    fn test_full_evaluation() {
+        assert_eq!(evaluate_full(&GameState::new(), Color::White), 0);
+        let fen = "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5";
+        let mirrored = "rnbqk2r/ppp2ppp/3p1n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQK2R b KQkq - 1 5";
+        assert_eq!(
+            evaluate_full(&GameState::from_fen(fen).unwrap(), Color::White),
+            evaluate_full(&GameState::from_fen(mirrored).unwrap(), Color::Black)
+        );
+    }
+
+    /// Only values a white pawn on e4.
+    struct KingPawnEvaluator;
+    impl Evaluator for KingPawnEvaluator {
+        fn evaluate(&self, game_state: &GameState, player_color: Color) -> i32 {
+            let score = match game_state.board[3][4] {
+                Some(piece) if piece.piece_type == PieceType::Pawn => 100,
+                _ => 0,
+            };
+            match player_color {
+                Color::White => score,
+                Color::Black => -score,
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        let king_pawn = Movement::Normal {
+            from: [1, 4],
+            to: [3, 4],
+            promotion: None,
+        };
+        let mut alphabeta = AlphaBetaBot::new(1);
+        alphabeta.set_evaluator(KingPawnEvaluator);
+        let mut minimax = MinimaxBot::new(2);
+        minimax.set_evaluator(KingPawnEvaluator);
+        let bots: [Box<dyn Controller>; 2] = [Box::new(alphabeta), Box::new(minimax)];
+        for mut bot in bots {
+            let Command::Move(movement) = bot.choose_command(&mut GameState::new(), None) else {
+                panic!("The bot didn't move");
+            };
+            assert_eq!(movement, king_pawn);
+        }
+
+        // functions and closures are evaluators too
+        let game_state = GameState::new();
+        assert_eq!(evaluate_material.evaluate(&game_state, Color::White), 0);
+        let constant = |_: &GameState, _: Color| 7;
+        assert_eq!(constant.evaluate(&game_state, Color::Black), 7);
+
+        // the bots accept a draw when their evaluator says that they are losing
+        let mut alphabeta = AlphaBetaBot::new(1);
+        let mut minimax = MinimaxBot::new(2);
+        alphabeta.set_evaluator(|_: &GameState, _: Color| 1);
+        minimax.set_evaluator(|_: &GameState, _: Color| 1);
+        assert!(!alphabeta.accept_draw(&game_state));
+        assert!(!minimax.accept_draw(&game_state));
+        alphabeta.set_evaluator(|_: &GameState, _: Color| -1);
+        minimax.set_evaluator(|_: &GameState, _: Color| -1);
+        assert!(alphabeta.accept_draw(&game_state));
+        assert!(minimax.accept_draw(&game_state));
+    }
+}
@@ -0,0 +1,324 @@
+mod common;
+
+use common::{normal, ScriptedController};
+use rust_chess::controllers::alphabeta::AlphaBetaBot;
+use rust_chess::controllers::controller::Command;
+use rust_chess::game::{Game, GameResult, Termination};
+use rust_chess::model::game_state::GameState;
+use rust_chess::model::piece::Color;
+use rust_chess::notation::pgn::write_pgn;
+use rust_chess::rules::cmd_validator::is_valid_cmd;
+use rust_chess::rules::game_over::{
+    get_game_result, is_draw, is_fifty_move_rule, is_fivefold_repetition,
+    is_seventy_five_move_rule, is_threefold_repetition,
+};
+use rust_chess::view::NoDisplay;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both players move a knight out and back: Nf3 Nf6 Ng1 Ng8.
+    const KNIGHT_SHUFFLE: [([usize; 2], [usize; 2]); 4] = [
+        ([0, 6], [2, 5]),
+        ([7, 6], [5, 5]),
+        ([2, 5], [0, 6]),
+        ([5, 5], [7, 6]),
+    ];
+
+    #[test]
+    fn test_zobrist_hash() {
+        let initial_hash = GameState::new().zobrist_hash();
+        assert_eq!(initial_hash, GameState::new().zobrist_hash());
+
+        // the same position reached with different movement orders
+        let mut game_state1 = GameState::new();
+        let mut game_state2 = GameState::new();
+        for (from, to) in [([0, 6], [2, 5]), ([7, 6], [5, 5]), ([0, 1], [2, 2])] {
+            game_state1.make_movement(normal(from, to));
+        }
+        for (from, to) in [([0, 1], [2, 2]), ([7, 6], [5, 5]), ([0, 6], [2, 5])] {
+            game_state2.make_movement(normal(from, to));
+        }
+        assert_eq!(game_state1.zobrist_hash(), game_state2.zobrist_hash());
+        assert_ne!(game_state1.zobrist_hash(), initial_hash);
+
+        // same board with a different player to move
+        let white_to_move = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let black_to_move = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(white_to_move.zobrist_hash(), black_to_move.zobrist_hash());
+
+        // castling rights are part of the position
+        let castling = GameState::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let no_castling = GameState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_ne!(castling.zobrist_hash(), no_castling.zobrist_hash());
+
+        // en passant only matters if a pawn can make the capture
+        let en_passant = GameState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let no_en_passant = GameState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(en_passant.zobrist_hash(), no_en_passant.zobrist_hash());
+        let en_passant = GameState::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1").unwrap();
+        let no_en_passant = GameState::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(en_passant.zobrist_hash(), no_en_passant.zobrist_hash());
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let mut game_state = GameState::new();
+        assert_eq!(game_state.repetition_count(), 1);
+        for (from, to) in KNIGHT_SHUFFLE {
+            game_state.make_movement(normal(from, to));
+        }
+        assert_eq!(game_state.repetition_count(), 2);
+        assert!(!is_threefold_repetition(&game_state));
+        assert!(!is_valid_cmd(&Command::ClaimDraw, &game_state, None));
+        for (from, to) in KNIGHT_SHUFFLE {
+            game_state.make_movement(normal(from, to));
+        }
+        assert_eq!(game_state.repetition_count(), 3);
+        assert!(is_threefold_repetition(&game_state));
+        assert!(is_valid_cmd(&Command::ClaimDraw, &game_state, None));
+        assert!(!is_draw(&game_state));
+        for _ in 0..2 {
+            for (from, to) in KNIGHT_SHUFFLE {
+                game_state.make_movement(normal(from, to));
+            }
+        }
+        assert!(is_fivefold_repetition(&game_state));
+        assert!(is_draw(&game_state));
+
+        // a pawn movement makes the previous positions unreachable
+        game_state.make_movement(normal([1, 4], [3, 4]));
+        assert!(game_state.position_history.is_empty());
+        assert_eq!(game_state.repetition_count(), 1);
+    }
+
+    #[test]
+    fn test_halfmove_clock() {
+        let mut game_state = GameState::new();
+        game_state.make_movement(normal([0, 6], [2, 5]));
+        game_state.make_movement(normal([7, 6], [5, 5]));
+        assert_eq!(game_state.halfmove_clock, 2);
+        game_state.make_movement(normal([1, 4], [3, 4]));
+        assert_eq!(game_state.halfmove_clock, 0);
+        game_state.make_movement(normal([7, 1], [5, 2]));
+        game_state.make_movement(normal([0, 5], [3, 2]));
+        assert_eq!(game_state.halfmove_clock, 2);
+        game_state.make_movement(normal([5, 5], [3, 4]));
+        assert_eq!(game_state.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn test_fifty_move_rule() {
+        let mut game_state = GameState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert!(!is_fifty_move_rule(&game_state));
+        game_state.make_movement(normal([0, 0], [1, 0]));
+        assert!(is_fifty_move_rule(&game_state));
+        assert!(is_valid_cmd(&Command::ClaimDraw, &game_state, None));
+        assert!(!is_draw(&game_state));
+
+        let mut game_state = GameState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 80").unwrap();
+        assert!(!is_seventy_five_move_rule(&game_state));
+        game_state.make_movement(normal([0, 0], [1, 0]));
+        assert!(is_seventy_five_move_rule(&game_state));
+        assert!(is_draw(&game_state));
+    }
+
+    #[test]
+    fn test_claim_draw() {
+        let mut white_commands = vec![];
+        let mut black_commands = vec![];
+        for _ in 0..2 {
+            for (i, (from, to)) in KNIGHT_SHUFFLE.into_iter().enumerate() {
+                let command = Command::Move(normal(from, to));
+                if i % 2 == 0 {
+                    white_commands.push(command);
+                } else {
+                    black_commands.push(command);
+                }
+            }
+        }
+        white_commands.push(Command::ClaimDraw);
+        let mut game = Game::new(
+            GameState::new(),
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: white_commands,
+                }),
+                Box::new(ScriptedController {
+                    commands: black_commands,
+                }),
+            ],
+        );
+        assert_eq!(
+            game.play(),
+            GameResult::Draw(Termination::ThreefoldRepetition)
+        );
+        assert_eq!(game.history.len(), 8);
+    }
+
+    #[test]
+    fn test_game_result() {
+        let game_state = GameState::new();
+        assert_eq!(get_game_result(&game_state), None);
+        let fools_mate =
+            GameState::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+                .unwrap();
+        let result = get_game_result(&fools_mate).unwrap();
+        assert_eq!(
+            result,
+            GameResult::Winner(Color::Black, Termination::Checkmate)
+        );
+        assert_eq!(result.to_string(), "Black wins by checkmate");
+        let stalemate = GameState::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            get_game_result(&stalemate),
+            Some(GameResult::Draw(Termination::Stalemate))
+        );
+        let insufficient_material = GameState::from_fen("7k/8/6K1/8/8/8/8/2B5 b - - 0 1").unwrap();
+        assert_eq!(
+            get_game_result(&insufficient_material),
+            Some(GameResult::Draw(Termination::InsufficientMaterial))
+        );
+        let seventy_five_moves = GameState::from_fen("7k/8/6K1/8/8/8/8/2R5 b - - 150 90").unwrap();
+        let result = get_game_result(&seventy_five_moves).unwrap();
+        assert_eq!(result, GameResult::Draw(Termination::SeventyFiveMoveRule));
+        assert_eq!(result.to_string(), "Draw by the seventy-five-move rule");
+    }
+
+    #[test]
+    fn test_resignation() {
+        let mut game = Game::new(
+            GameState::new(),
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![Command::Move(normal([1, 4], [3, 4])), Command::Resign],
+                }),
+                Box::new(ScriptedController {
+                    commands: vec![Command::Move(normal([6, 4], [4, 4]))],
+                }),
+            ],
+        );
+        let result = game.play();
+        assert_eq!(
+            result,
+            GameResult::Winner(Color::Black, Termination::Resignation)
+        );
+        assert_eq!(game.result, Some(result));
+        assert!(write_pgn(&game).contains("1. e4 e5 0-1"));
+    }
+
+    #[test]
+    fn test_draw_offer() {
+        // the bot accepts the offer when it is losing
+        let game_state = GameState::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let mut game = Game::new(
+            game_state,
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![Command::OfferDraw],
+                }),
+                Box::new(AlphaBetaBot::new(2)),
+            ],
+        );
+        assert_eq!(game.play(), GameResult::Draw(Termination::Agreement));
+
+        // and declines it otherwise, so the game continues
+        let game_state = GameState::from_fen("3qk3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut game = Game::new(
+            game_state,
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![Command::OfferDraw, Command::Resign],
+                }),
+                Box::new(AlphaBetaBot::new(2)),
+            ],
+        );
+        assert_eq!(
+            game.play(),
+            GameResult::Winner(Color::Black, Termination::Resignation)
+        );
+    }
+
+    #[test]
+    fn test_draw_reply() {
+        let game_state = GameState::new();
+        // a reply needs a pending offer from the opponent
+        for reply in [Command::AcceptDraw, Command::DeclineDraw] {
+            assert!(!is_valid_cmd(&reply, &game_state, None));
+            assert!(!is_valid_cmd(&reply, &game_state, Some(Color::White)));
+            assert!(is_valid_cmd(&reply, &game_state, Some(Color::Black)));
+        }
+
+        // without an offer the reply is ignored and the game goes on
+        let mut game = Game::new(
+            GameState::new(),
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![Command::AcceptDraw, Command::Resign],
+                }),
+                Box::new(ScriptedController { commands: vec![] }),
+            ],
+        );
+        assert_eq!(
+            game.play(),
+            GameResult::Winner(Color::Black, Termination::Resignation)
+        );
+
+        // the offer is declined by the opponent's movement
+        let mut game = Game::new(
+            GameState::new(),
+            Box::new(NoDisplay),
+            [
+                Box::new(ScriptedController {
+                    commands: vec![
+                        Command::Move(normal([1, 4], [3, 4])),
+                        Command::AcceptDraw,
+                        Command::Resign,
+                    ],
+                }),
+                Box::new(ScriptedController {
+                    commands: vec![Command::Move(normal([6, 4], [4, 4]))],
+                }),
+            ],
+        );
+        game.draw_offer = Some(Color::Black);
+        game.player_turn();
+        assert_eq!(game.draw_offer, None);
+        assert_eq!(
+            game.play(),
+            GameResult::Winner(Color::Black, Termination::Resignation)
+        );
+
+        // a pending offer can be accepted or declined
+        for (reply, result) in [
+            (
+                Command::AcceptDraw,
+                GameResult::Draw(Termination::Agreement),
+            ),
+            (
+                Command::DeclineDraw,
+                GameResult::Winner(Color::Black, Termination::Resignation),
+            ),
+        ] {
+            let mut game = Game::new(
+                GameState::new(),
+                Box::new(NoDisplay),
+                [
+                    Box::new(ScriptedController {
+                        commands: vec![reply, Command::AcceptDraw, Command::Resign],
+                    }),
+                    Box::new(ScriptedController { commands: vec![] }),
+                ],
+            );
+            game.draw_offer = Some(Color::Black);
+            assert_eq!(game.play(), result);
+            assert_eq!(game.draw_offer, None);
+        }
+    }
+}
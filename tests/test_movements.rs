@@ -1,8 +1,12 @@
+use rand::seq::SliceRandom;
 use rand::Rng;
+use rust_chess::model::bitboard::Bitboards;
 use rust_chess::model::movement::Movement;
 use rust_chess::model::{game_state::GameState, piece::Color};
 use rust_chess::rules::cmd_validator::{is_in_check, is_valid_movement};
-use rust_chess::rules::move_generator::generate_movements;
+use rust_chess::rules::move_generator::{
+    generate_movements, generate_movements_for_player_ignoring_check,
+};
 
 #[cfg(test)]
 mod tests {
@@ -17,16 +21,19 @@ mod tests {
 
     pub const fn new_empty_game_state() -> GameState {
         GameState {
-            board: [[None; 8]; 8],
+            board: Bitboards::new([[None; 8]; 8]),
             player_to_move: Color::White,
             last_move: None,
+            en_passant_square: None,
             white_can_castle_queen_side: true,
             white_can_castle_king_side: true,
             black_can_castle_king_side: true,
             black_can_castle_queen_side: true,
             king_initial_positions: [None, None],
             rook_initial_positions: [[None, None], [None, None]],
-            move_limit: 100,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            position_history: Vec::new(),
         }
     }
 
@@ -38,22 +45,31 @@ mod tests {
         let mut rng = rand::thread_rng();
         let x: usize = rng.gen_range(1, 3);
         let y: usize = rng.gen_range(1, 3);
-        game_state.board[x][y] = Some(Piece {
-            piece_type: PieceType::King,
-            color: Color::White,
-        });
+        game_state.board.set(
+            [x, y],
+            Some(Piece {
+                piece_type: PieceType::King,
+                color: Color::White,
+            }),
+        );
         let x2: usize = rng.gen_range(5, 7);
         let y2: usize = rng.gen_range(5, 7);
-        game_state.board[x2][y2] = Some(Piece {
-            piece_type: PieceType::King,
-            color: Color::Black,
-        });
+        game_state.board.set(
+            [x2, y2],
+            Some(Piece {
+                piece_type: PieceType::King,
+                color: Color::Black,
+            }),
+        );
         assert_eq!(is_in_check(&game_state, Color::White), false);
         assert_eq!(is_in_check(&game_state, Color::Black), false);
-        game_state.board[x + 1][y + 1] = Some(Piece {
-            piece_type: PieceType::Queen,
-            color: Color::Black,
-        });
+        game_state.board.set(
+            [x + 1, y + 1],
+            Some(Piece {
+                piece_type: PieceType::Queen,
+                color: Color::Black,
+            }),
+        );
         assert_eq!(is_in_check(&game_state, Color::White), true);
         assert_eq!(is_in_check(&game_state, Color::Black), false);
     }
@@ -64,10 +80,13 @@ mod tests {
         let mut rng = rand::thread_rng();
         let x: usize = rng.gen_range(0, 7);
         let y: usize = rng.gen_range(0, 7);
-        one_rook_state.board[x][y] = Some(Piece {
-            piece_type: PieceType::Rook,
-            color: Color::White,
-        });
+        one_rook_state.board.set(
+            [x, y],
+            Some(Piece {
+                piece_type: PieceType::Rook,
+                color: Color::White,
+            }),
+        );
         let movements: Vec<Movement> = generate_movements(&one_rook_state);
         assert_eq!(movements.len(), 14);
     }
@@ -75,10 +94,13 @@ mod tests {
     #[test]
     fn test_bishop() {
         let mut one_bishop_state = new_empty_game_state();
-        one_bishop_state.board[1][2] = Some(Piece {
-            piece_type: PieceType::Bishop,
-            color: Color::White,
-        });
+        one_bishop_state.board.set(
+            [1, 2],
+            Some(Piece {
+                piece_type: PieceType::Bishop,
+                color: Color::White,
+            }),
+        );
         let movements: Vec<Movement> = generate_movements(&one_bishop_state);
         assert_eq!(movements.len(), 9);
     }
@@ -89,16 +111,22 @@ mod tests {
         let mut rng = rand::thread_rng();
         let x: usize = rng.gen_range(1, 6);
         let y: usize = rng.gen_range(1, 6);
-        one_king_state.board[x][y] = Some(Piece {
-            piece_type: PieceType::King,
-            color: Color::White,
-        });
+        one_king_state.board.set(
+            [x, y],
+            Some(Piece {
+                piece_type: PieceType::King,
+                color: Color::White,
+            }),
+        );
         let movements: Vec<Movement> = generate_movements(&one_king_state);
         assert_eq!(movements.len(), 8);
-        one_king_state.board[x + 1][y + 1] = Some(Piece {
-            piece_type: PieceType::Queen,
-            color: Color::Black,
-        });
+        one_king_state.board.set(
+            [x + 1, y + 1],
+            Some(Piece {
+                piece_type: PieceType::Queen,
+                color: Color::Black,
+            }),
+        );
         let movements2: Vec<Movement> = generate_movements(&one_king_state);
         assert_eq!(movements2.len(), 3);
     }
@@ -111,7 +139,8 @@ mod tests {
             is_valid_movement(
                 &Movement::Normal {
                     from: [4, 3],
-                    to: [5, 4]
+                    to: [5, 4],
+                    promotion: None,
                 },
                 &en_passant_state
             ),
@@ -119,40 +148,164 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_en_passant_square() {
+        let mut game_state = GameState::new();
+        for [from, to] in [[[1, 4], [3, 4]], [[6, 0], [5, 0]], [[3, 4], [4, 4]]] {
+            game_state.make_movement(Movement::Normal {
+                from,
+                to,
+                promotion: None,
+            });
+        }
+        assert_eq!(game_state.en_passant_square, None);
+        game_state.make_movement(Movement::Normal {
+            from: [6, 3],
+            to: [4, 3],
+            promotion: None,
+        });
+        assert_eq!(game_state.en_passant_square, Some([5, 3]));
+        let en_passant = Movement::Normal {
+            from: [4, 4],
+            to: [5, 3],
+            promotion: None,
+        };
+        assert!(generate_movements(&game_state).contains(&en_passant));
+        game_state.make_movement(en_passant);
+        assert_eq!(game_state.en_passant_square, None);
+        assert_eq!(game_state.board[4][3], None);
+
+        // the right to capture en passant is lost after one movement
+        let mut game_state = GameState::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        game_state.make_movement(Movement::Normal {
+            from: [1, 4],
+            to: [3, 4],
+            promotion: None,
+        });
+        let en_passant = Movement::Normal {
+            from: [3, 3],
+            to: [2, 4],
+            promotion: None,
+        };
+        assert!(is_valid_movement(&en_passant, &game_state));
+        let loaded_state = GameState::from_fen(&game_state.to_fen()).unwrap();
+        assert!(is_valid_movement(&en_passant, &loaded_state));
+        assert_eq!(loaded_state, game_state);
+        game_state.make_movement(Movement::Normal {
+            from: [7, 4],
+            to: [7, 3],
+            promotion: None,
+        });
+        game_state.make_movement(Movement::Normal {
+            from: [0, 4],
+            to: [0, 3],
+            promotion: None,
+        });
+        assert!(!is_valid_movement(&en_passant, &game_state));
+    }
+
+    #[test]
+    fn test_promotion() {
+        let mut game_state = new_empty_game_state();
+        game_state.board.set(
+            [6, 1],
+            Some(Piece {
+                piece_type: PieceType::Pawn,
+                color: Color::White,
+            }),
+        );
+        game_state.board.set(
+            [7, 0],
+            Some(Piece {
+                piece_type: PieceType::Rook,
+                color: Color::Black,
+            }),
+        );
+        let movements: Vec<Movement> = generate_movements(&game_state);
+        assert_eq!(movements.len(), 8);
+        for piece_type in [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ] {
+            let movement = Movement::Normal {
+                from: [6, 1],
+                to: [7, 0],
+                promotion: Some(piece_type),
+            };
+            assert!(movements.contains(&movement));
+            assert!(is_valid_movement(&movement, &game_state));
+            let game_state2 = game_state.clone_and_move(movement);
+            assert_eq!(
+                game_state2.board[7][0],
+                Some(Piece {
+                    piece_type,
+                    color: Color::White
+                })
+            );
+        }
+        for piece_type in [PieceType::King, PieceType::Pawn] {
+            let movement = Movement::Normal {
+                from: [6, 1],
+                to: [7, 1],
+                promotion: Some(piece_type),
+            };
+            assert!(!is_valid_movement(&movement, &game_state));
+        }
+        // only pawns reaching the last row can be promoted
+        game_state.board.set(
+            [3, 3],
+            Some(Piece {
+                piece_type: PieceType::Pawn,
+                color: Color::White,
+            }),
+        );
+        let movement = Movement::Normal {
+            from: [3, 3],
+            to: [4, 3],
+            promotion: Some(PieceType::Knight),
+        };
+        assert!(!is_valid_movement(&movement, &game_state));
+    }
+
     #[test]
     fn test_castle() {
         let mut game_state = GameState::new();
         assert_eq!(king_castle_is_valid(&game_state), false);
         assert_eq!(queen_castle_is_valid(&game_state), false);
-        game_state.board[0][5] = None;
-        game_state.board[0][6] = None;
+        game_state.board.set([0, 5], None);
+        game_state.board.set([0, 6], None);
         assert_eq!(king_castle_is_valid(&game_state), true);
         game_state.make_movement(Movement::Normal {
             from: [0, 4],
             to: [0, 5],
+            promotion: None,
         });
         game_state.make_movement(Movement::Normal {
             from: [0, 5],
             to: [0, 4],
+            promotion: None,
         });
         assert_eq!(king_castle_is_valid(&game_state), false);
-        game_state.board[7][5] = None;
-        game_state.board[7][6] = None;
+        game_state.board.set([7, 5], None);
+        game_state.board.set([7, 6], None);
         game_state.player_to_move = Color::Black;
         assert_eq!(king_castle_is_valid(&game_state), true);
         assert_eq!(queen_castle_is_valid(&game_state), false);
-        game_state.board[7][1] = None;
-        game_state.board[7][2] = None;
-        game_state.board[7][3] = None;
+        game_state.board.set([7, 1], None);
+        game_state.board.set([7, 2], None);
+        game_state.board.set([7, 3], None);
         assert_eq!(queen_castle_is_valid(&game_state), true);
         game_state.make_movement(Movement::Normal {
             from: [0, 0],
             to: [7, 0],
+            promotion: None,
         }); // take the rook
         assert_eq!(queen_castle_is_valid(&game_state), false);
-        game_state.board[0][1] = None;
-        game_state.board[0][2] = None;
-        game_state.board[0][3] = None;
+        game_state.board.set([0, 1], None);
+        game_state.board.set([0, 2], None);
+        game_state.board.set([0, 3], None);
         game_state.player_to_move = Color::White;
         assert_eq!(queen_castle_is_valid(&game_state), false);
     }
@@ -167,6 +320,7 @@ mod tests {
         state960.make_movement(Movement::Normal {
             from: [0, 7],
             to: [2, 6],
+            promotion: None,
         });
         assert_eq!(queen_castle_is_valid(&state960), false);
         assert_eq!(king_castle_is_valid(&state960), true);
@@ -174,6 +328,7 @@ mod tests {
         state960.make_movement(Movement::Normal {
             from: [6, 7],
             to: [5, 6],
+            promotion: None,
         });
         AsciiDisplay.display_game(&state960);
         assert_eq!(queen_castle_is_valid(&state960), false);
@@ -182,20 +337,24 @@ mod tests {
         state960.make_movement(Movement::Normal {
             from: [0, 6],
             to: [0, 7],
+            promotion: None,
         });
         assert_eq!(state960.white_can_castle_queen_side, true);
         assert_eq!(state960.white_can_castle_king_side, false);
-        assert_eq!(king_castle_is_valid(&state960), false);
+        // black to move: the king and the rook next to it only swap their squares
+        assert!(king_castle_is_valid(&state960));
         assert_eq!(queen_castle_is_valid(&state960), false);
         // move black pawn
         state960.make_movement(Movement::Normal {
             from: [6, 7],
             to: [5, 7],
+            promotion: None,
         });
         // move white rook back to initial position
         state960.make_movement(Movement::Normal {
             from: [0, 7],
             to: [0, 6],
+            promotion: None,
         });
         assert_eq!(queen_castle_is_valid(&state960), false);
         assert_eq!(king_castle_is_valid(&state960), true);
@@ -203,12 +362,14 @@ mod tests {
         state960.make_movement(Movement::Normal {
             from: [7, 6],
             to: [6, 6],
+            promotion: None,
         });
         assert_eq!(queen_castle_is_valid(&state960), false);
         assert_eq!(king_castle_is_valid(&state960), false);
         state960.make_movement(Movement::Normal {
             from: [1, 0],
             to: [2, 0],
+            promotion: None,
         });
         assert_eq!(queen_castle_is_valid(&state960), false);
         assert_eq!(king_castle_is_valid(&state960), false);
@@ -238,4 +399,97 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_make_unmake_move() {
+        let kiwipete = GameState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let mut rng = rand::thread_rng();
+        for mut game_state in [GameState::new(), GameState::new960(), kiwipete] {
+            for _ in 0..100 {
+                let movements = generate_movements(&game_state);
+                let Some(chosen_movement) = movements.choose(&mut rng).cloned() else {
+                    break;
+                };
+                let saved_state = serde_json::to_string(&game_state).unwrap();
+                let saved_board = game_state.board;
+                for movement in movements {
+                    let next_state = game_state.clone_and_move(movement.clone());
+                    let undo = game_state.make_move(movement);
+                    assert_eq!(
+                        serde_json::to_string(&game_state).unwrap(),
+                        serde_json::to_string(&next_state).unwrap()
+                    );
+                    assert_eq!(game_state.board, next_state.board);
+                    game_state.unmake_move(undo);
+                    // every field is restored, including the clocks and the position history
+                    assert_eq!(serde_json::to_string(&game_state).unwrap(), saved_state);
+                    assert_eq!(game_state.board, saved_board);
+                }
+                game_state.make_move(chosen_movement);
+            }
+        }
+    }
+
+    /// Pseudo-legal movements that don't leave the king in check.
+    fn slow_legal_movements(game_state: &GameState) -> Vec<Movement> {
+        let player = game_state.player_to_move;
+        generate_movements_for_player_ignoring_check(game_state, player)
+            .into_iter()
+            .filter(|movement| !is_in_check(&game_state.clone_and_move(movement.clone()), player))
+            .collect()
+    }
+
+    #[test]
+    fn test_legal_movements() {
+        // pinned pieces, double check and the en passant capture that uncovers the king
+        for fen in [
+            "4k3/8/8/8/8/8/4R3/4K3 b - - 0 1",
+            "4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1",
+            "4k3/8/8/1b6/8/8/4N3/4K2r w - - 0 1",
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 2",
+            "8/8/3k4/2pP4/8/8/8/B6K w - c6 0 2",
+            "4k3/8/8/8/1b6/8/3r4/R3K2R w KQ - 0 1",
+        ] {
+            let game_state = GameState::from_fen(fen).unwrap();
+            let mut movements = generate_movements(&game_state);
+            let mut expected = slow_legal_movements(&game_state);
+            let key = |movement: &Movement| format!("{:?}", movement);
+            movements.sort_by_key(key);
+            expected.sort_by_key(key);
+            assert_eq!(movements, expected, "{}", fen);
+        }
+        // the en passant capture would leave the king in check along the row
+        let game_state = GameState::from_fen("8/8/8/K2pP2r/8/8/8/7k w - d6 0 2").unwrap();
+        assert!(
+            !generate_movements(&game_state).contains(&Movement::Normal {
+                from: [4, 4],
+                to: [5, 3],
+                promotion: None,
+            })
+        );
+        // castling is not possible through an attacked square
+        let game_state = GameState::from_fen("4k3/8/8/8/8/8/3r4/R3K2R w KQ - 0 1").unwrap();
+        let movements = generate_movements(&game_state);
+        assert!(movements.contains(&Movement::CastleKingSide(Color::White)));
+        assert!(!movements.contains(&Movement::CastleQueenSide(Color::White)));
+
+        let mut rng = rand::thread_rng();
+        for mut game_state in [GameState::new(), GameState::new960()] {
+            for _ in 0..200 {
+                let mut movements = generate_movements(&game_state);
+                let mut expected = slow_legal_movements(&game_state);
+                let key = |movement: &Movement| format!("{:?}", movement);
+                movements.sort_by_key(key);
+                expected.sort_by_key(key);
+                assert_eq!(movements, expected);
+                let Some(movement) = movements.choose(&mut rng) else {
+                    break;
+                };
+                game_state.make_movement(movement.clone());
+            }
+        }
+    }
 }
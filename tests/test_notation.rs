@@ -0,0 +1,572 @@
+mod common;
+
+use common::normal;
+use rand::seq::SliceRandom;
+use rust_chess::controllers::controller::Command;
+use rust_chess::controllers::random_bot::RandomBot;
+use rust_chess::game::Game;
+use rust_chess::model::fen::FenError;
+use rust_chess::model::{
+    game_state::GameState,
+    movement::Movement,
+    piece::{Color, PieceType},
+};
+use rust_chess::notation::pgn::{read_pgn, write_pgn, PgnError, PgnReader};
+use rust_chess::notation::san::{movement_to_san, parse_movement, parse_san, MoveParseError};
+use rust_chess::rules::move_generator::generate_movements;
+use rust_chess::view::NoDisplay;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE_FEN: &str =
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn test_fen_start_position() {
+        assert_eq!(GameState::new().to_fen(), START_FEN);
+        assert_eq!(GameState::from_fen(START_FEN).unwrap(), GameState::new());
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        for fen in [
+            KIWIPETE_FEN,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        ] {
+            assert_eq!(GameState::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_fen_after_movements() {
+        let mut game_state = GameState::new();
+        game_state.make_movement(Movement::Normal {
+            from: [0, 6],
+            to: [2, 5],
+            promotion: None,
+        });
+        game_state.make_movement(Movement::Normal {
+            from: [7, 6],
+            to: [5, 5],
+            promotion: None,
+        });
+        assert_eq!(
+            game_state.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 2 2"
+        );
+        game_state.make_movement(Movement::Normal {
+            from: [1, 4],
+            to: [3, 4],
+            promotion: None,
+        });
+        assert_eq!(
+            game_state.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq e3 0 2"
+        );
+        assert_eq!(
+            GameState::from_fen(&game_state.to_fen()).unwrap().to_fen(),
+            game_state.to_fen()
+        );
+    }
+
+    #[test]
+    fn test_fen_960_castling() {
+        // X-FEN and Shredder-FEN describe the same position
+        let x_fen = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1";
+        let shredder_fen = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1";
+        let game_state = GameState::from_fen(x_fen).unwrap();
+        assert_eq!(game_state, GameState::from_fen(shredder_fen).unwrap());
+        assert_eq!(game_state.to_fen(), x_fen);
+        assert_eq!(
+            game_state.get_king_initial_position(Color::White),
+            Some([0, 5])
+        );
+        assert_eq!(
+            game_state.get_rook_initial_position(Color::Black, true),
+            Some([7, 6])
+        );
+        assert_eq!(
+            game_state.get_rook_initial_position(Color::Black, false),
+            Some([7, 4])
+        );
+
+        // the castling rook is not the outermost one, so its file must be given
+        let inner_rook_fen = "4k3/8/8/8/8/8/8/1R2K1RR w B - 0 1";
+        let game_state = GameState::from_fen(inner_rook_fen).unwrap();
+        assert!(game_state.white_can_castle_queen_side);
+        assert!(!game_state.white_can_castle_king_side);
+        assert_eq!(game_state.to_fen(), "4k3/8/8/8/8/8/8/1R2K1RR w Q - 0 1");
+        let game_state = GameState::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1").unwrap();
+        assert_eq!(
+            game_state.get_rook_initial_position(Color::White, true),
+            Some([0, 6])
+        );
+        assert_eq!(game_state.to_fen(), "4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1");
+    }
+
+    #[test]
+    fn test_fen_errors() {
+        assert_eq!(
+            GameState::from_fen("8/8/8/8/8/8/8 w - - 0 1"),
+            Err(FenError::WrongNumberOfRanks(7))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq -"),
+            Err(FenError::WrongNumberOfRanks(7))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            Err(FenError::InvalidRank("9".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            Err(FenError::InvalidRank("ppppppp".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1"),
+            Err(FenError::InvalidPiece('X'))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"),
+            Err(FenError::InvalidSideToMove("x".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            Err(FenError::InvalidCastlingRights("KQkq".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1"),
+            Err(FenError::InvalidEnPassantSquare("e3".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1"),
+            Err(FenError::InvalidHalfmoveClock("x".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0"),
+            Err(FenError::InvalidFullmoveNumber("0".to_string()))
+        );
+        assert_eq!(
+            GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"),
+            Err(FenError::WrongNumberOfFields(2))
+        );
+    }
+
+    fn san(fen: &str, from: [usize; 2], to: [usize; 2]) -> String {
+        movement_to_san(
+            &Movement::Normal {
+                from,
+                to,
+                promotion: None,
+            },
+            &GameState::from_fen(fen).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_san() {
+        assert_eq!(san(START_FEN, [0, 6], [2, 5]), "Nf3");
+        assert_eq!(san(START_FEN, [1, 4], [3, 4]), "e4");
+        // disambiguation by file, by rank and by square
+        assert_eq!(
+            san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", [0, 0], [0, 3]),
+            "Rad1"
+        );
+        assert_eq!(
+            san("4k3/8/8/R7/8/8/4K3/R7 w - - 0 1", [0, 0], [2, 0]),
+            "R1a3"
+        );
+        assert_eq!(
+            san("7k/8/8/8/8/Q7/8/Q1Q3K1 w - - 0 1", [0, 0], [1, 1]),
+            "Qa1b2+"
+        );
+        // a pinned piece does not need to be distinguished
+        assert_eq!(
+            san("4k3/8/8/8/8/8/8/1N2KN1r w - - 0 1", [0, 1], [1, 3]),
+            "Nd2"
+        );
+        assert_eq!(
+            san("4k3/8/3p4/4N3/8/8/8/4K3 w - - 0 1", [4, 4], [5, 3]),
+            "Nxd6+"
+        );
+        assert_eq!(
+            san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", [4, 4], [5, 3]),
+            "exd6"
+        );
+        assert_eq!(
+            san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", [6, 1], [7, 1]),
+            "b8=Q+"
+        );
+        let castle_state = GameState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(
+            movement_to_san(&Movement::CastleKingSide(Color::White), &castle_state),
+            "O-O"
+        );
+        assert_eq!(
+            movement_to_san(&Movement::CastleQueenSide(Color::White), &castle_state),
+            "O-O-O"
+        );
+    }
+
+    #[test]
+    fn test_movement_to_san() {
+        let game_state =
+            GameState::from_fen("rnbqkbnr/ppppp2p/5p2/6p1/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3")
+                .unwrap();
+        let mate = Movement::Normal {
+            from: [0, 3],
+            to: [4, 7],
+            promotion: None,
+        };
+        assert_eq!(mate.to_san(&game_state), "Qh5#");
+        let check = GameState::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        assert_eq!(
+            Movement::CastleQueenSide(Color::White).to_san(&check),
+            "O-O-O"
+        );
+        assert_eq!(
+            Movement::Normal {
+                from: [0, 0],
+                to: [7, 0],
+                promotion: None,
+            }
+            .to_san(&check),
+            "Ra8+"
+        );
+        let promotion = GameState::from_fen("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            Movement::Normal {
+                from: [6, 1],
+                to: [7, 0],
+                promotion: None,
+            }
+            .to_san(&promotion),
+            "bxa8=Q+"
+        );
+    }
+
+    fn new_game(game_state: GameState) -> Game {
+        Game::new(
+            game_state,
+            Box::new(NoDisplay),
+            [Box::new(RandomBot), Box::new(RandomBot)],
+        )
+    }
+
+    #[test]
+    fn test_pgn_export() {
+        let mut game = new_game(GameState::new());
+        for (from, to) in [
+            ([1, 5], [2, 5]),
+            ([6, 4], [4, 4]),
+            ([1, 6], [3, 6]),
+            ([7, 3], [3, 7]),
+        ] {
+            game.execute_command(Command::Move(Movement::Normal {
+                from,
+                to,
+                promotion: None,
+            }));
+        }
+        let pgn = write_pgn(&game);
+        assert!(pgn.starts_with("[Event "));
+        for tag in ["Site", "Date", "Round", "White", "Black"] {
+            assert!(pgn.contains(&format!("\n[{} \"", tag)));
+        }
+        assert!(pgn.contains("[Result \"0-1\"]\n"));
+        assert!(!pgn.contains("[FEN "));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn test_pgn_export_from_position() {
+        let fen = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN b KQkq - 0 5";
+        let mut game = new_game(GameState::from_fen(fen).unwrap());
+        game.execute_command(Command::Move(Movement::Normal {
+            from: [7, 2],
+            to: [5, 3],
+            promotion: None,
+        }));
+        let pgn = write_pgn(&game);
+        assert!(pgn.contains("[Variant \"Chess960\"]\n"));
+        assert!(pgn.contains("[SetUp \"1\"]\n"));
+        assert!(pgn.contains(&format!("[FEN \"{}\"]\n", fen)));
+        assert!(pgn.contains("[Result \"*\"]\n"));
+        assert!(pgn.ends_with("\n\n5... Nd6 *\n"));
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let game_state = GameState::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(
+            parse_san("Rad1", &game_state),
+            Ok(Movement::Normal {
+                from: [0, 0],
+                to: [0, 3],
+                promotion: None,
+            })
+        );
+        assert_eq!(
+            parse_san("Rd1", &game_state),
+            Err(MoveParseError::AmbiguousMovement("Rd1".to_string()))
+        );
+        assert_eq!(
+            parse_san("Rd2", &game_state),
+            Err(MoveParseError::IllegalMovement("Rd2".to_string()))
+        );
+        assert_eq!(
+            parse_san("Zd1", &game_state),
+            Err(MoveParseError::InvalidSyntax("Zd1".to_string()))
+        );
+        let game_state = GameState::new();
+        assert_eq!(
+            parse_san("Nf3!?", &game_state),
+            Ok(Movement::Normal {
+                from: [0, 6],
+                to: [2, 5],
+                promotion: None,
+            })
+        );
+        assert_eq!(
+            parse_san("exd5", &game_state),
+            Err(MoveParseError::IllegalMovement("exd5".to_string()))
+        );
+
+        // captures are only read with 'x', and 'x' only for captures
+        let game_state = GameState::from_fen("4k3/8/8/4p3/3P4/5n2/8/K5N1 w - - 0 1").unwrap();
+        for (san, is_legal) in [
+            ("dxe5", true),
+            ("de5", false),
+            ("Nxf3", true),
+            ("Nf3", false),
+            ("Ne2", true),
+            ("Nxe2", false),
+            ("dxd5", false),
+        ] {
+            let result = parse_san(san, &game_state);
+            if is_legal {
+                assert!(result.is_ok(), "{}", san);
+            } else {
+                assert_eq!(
+                    result,
+                    Err(MoveParseError::IllegalMovement(san.to_string()))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_movement() {
+        let game_state = GameState::new();
+        for text in ["e4", "e2-e4", "e2e4", "e4+"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Ok(normal([1, 4], [3, 4]))
+            );
+        }
+        for text in ["Nf3", "Ngf3", "Ng1f3", "Ng1-f3", "g1f3"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Ok(normal([0, 6], [2, 5]))
+            );
+        }
+        for text in ["Ng1xf3", "Bg1-f3", "e2-e5", "e7e5", "e4e5", "Nf4"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Err(MoveParseError::IllegalMovement(text.to_string()))
+            );
+        }
+        for text in ["", "e", "e9", "Nf3f", "hello"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Err(MoveParseError::InvalidSyntax(text.to_string()))
+            );
+        }
+
+        let game_state =
+            GameState::from_fen("r3k2r/1P4n1/8/8/8/5N2/4N3/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(
+            parse_movement("Nd4", &game_state),
+            Err(MoveParseError::AmbiguousMovement("Nd4".to_string()))
+        );
+        for text in ["Nfd4", "N3d4", "Nf3d4", "Nf3-d4", "f3d4"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Ok(normal([2, 5], [3, 3]))
+            );
+        }
+        for text in ["O-O", "0-0", "e1g1", "e1h1", "Ke1-g1"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Ok(Movement::CastleKingSide(Color::White))
+            );
+        }
+        for text in ["O-O-O", "e1c1", "e1a1"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Ok(Movement::CastleQueenSide(Color::White))
+            );
+        }
+        for (texts, piece_type) in [
+            (["bxa8=Q", "bxa8Q", "b7xa8=Q", "b7a8q"], PieceType::Queen),
+            (["bxa8=N", "bxa8N", "b7xa8=N", "b7a8n"], PieceType::Knight),
+            (["bxa8=R", "bxa8R", "b7xa8=R", "b7a8r"], PieceType::Rook),
+        ] {
+            for text in texts {
+                assert_eq!(
+                    parse_movement(text, &game_state),
+                    Ok(Movement::Normal {
+                        from: [6, 1],
+                        to: [7, 0],
+                        promotion: Some(piece_type)
+                    })
+                );
+            }
+        }
+        for text in ["bxa8", "b7a8", "b8"] {
+            assert_eq!(
+                parse_movement(text, &game_state),
+                Err(MoveParseError::AmbiguousMovement(text.to_string()))
+            );
+        }
+        assert_eq!(
+            parse_movement("bxa8=K", &game_state),
+            Err(MoveParseError::IllegalMovement("bxa8=K".to_string()))
+        );
+        assert_eq!(
+            parse_movement("Nf3=Q", &game_state),
+            Err(MoveParseError::InvalidSyntax("Nf3=Q".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..5 {
+            let mut game_state = GameState::new960();
+            for _ in 0..60 {
+                let movements = generate_movements(&game_state);
+                for movement in &movements {
+                    let san = movement.to_san(&game_state);
+                    assert_eq!(parse_movement(&san, &game_state).as_ref(), Ok(movement));
+                }
+                let Some(movement) = movements.choose(&mut rng) else {
+                    break;
+                };
+                game_state.make_movement(movement.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_pgn_import() {
+        let pgn = r#"[Event "Test \"quoted\""]
+[Site "?"]
+[Result "1-0"]
+
+% escaped line with ( and {
+1. e4 {best by test} e5 $1 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 ; comment
+3. Bb5 a6!? 4. Ba4 Nf6 5. O-O Be7 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4K3/R6R w - - 0 1"]
+
+1. Rad1 Kf7 *
+"#;
+        let games = read_pgn(pgn).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].get_tag("Event"), Some("Test \"quoted\""));
+        assert_eq!(games[0].result, "1-0");
+        assert_eq!(games[0].movements.len(), 10);
+        assert_eq!(
+            games[0].movements[9],
+            Movement::Normal {
+                from: [7, 5],
+                to: [6, 4],
+                promotion: None,
+            }
+        );
+        let game_states = games[0].game_states();
+        assert_eq!(game_states.len(), 11);
+        assert!(game_states[10]
+            .to_fen()
+            .starts_with("r1bqk2r/1pppbppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - "));
+        assert_eq!(games[1].result, "*");
+        assert_eq!(
+            games[1].game_states()[2].to_fen(),
+            "8/5k2/8/8/8/8/4K3/3R3R w - - 2 2"
+        );
+    }
+
+    #[test]
+    fn test_pgn_import_errors() {
+        let pgn = "1. e4 e5 2. Ke3 Nc6 1-0\n\n[Event \"Next\"]\n\n1. d4 *\n";
+        let mut games = PgnReader::new(pgn.as_bytes());
+        match games.next() {
+            Some(Err(PgnError::InvalidMovement { move_number, error })) => {
+                assert_eq!(move_number, 2);
+                assert_eq!(error, MoveParseError::IllegalMovement("Ke3".to_string()));
+            }
+            _ => panic!("expected an illegal movement"),
+        }
+        // the reader continues with the next game
+        let game = games.next().unwrap().unwrap();
+        assert_eq!(game.get_tag("Event"), Some("Next"));
+        assert_eq!(game.movements.len(), 1);
+        assert!(games.next().is_none());
+
+        // movements of the variations are checked from the position where they branch off
+        let pgn =
+            "1. e4 e5 (1... Ke7) 2. Nf3 *\n\n1. e4 (1... e5) *\n\n1. e4 e5 (1... e5 2. Qh5) *\n";
+        let mut games = PgnReader::new(pgn.as_bytes());
+        match games.next() {
+            Some(Err(PgnError::InvalidMovement { move_number, error })) => {
+                assert_eq!(move_number, 1);
+                assert_eq!(error, MoveParseError::IllegalMovement("Ke7".to_string()));
+            }
+            _ => panic!("expected an illegal movement in the variation"),
+        }
+        // the variation replaces 1. e4, so it starts with a White movement
+        assert!(matches!(
+            games.next(),
+            Some(Err(PgnError::InvalidMovement { .. }))
+        ));
+        let game = games.next().unwrap().unwrap();
+        assert_eq!(game.movements.len(), 2);
+        assert!(matches!(
+            read_pgn("(1. e4) 1. d4 *"),
+            Err(PgnError::UnexpectedToken(_))
+        ));
+        assert!(matches!(
+            read_pgn("1. e4 (1. d4 *"),
+            Err(PgnError::UnexpectedToken(_))
+        ));
+    }
+
+    #[test]
+    fn test_pgn_round_trip() {
+        let mut game = new_game(GameState::new960());
+        for _ in 0..40 {
+            game.player_turn();
+        }
+        let pgn_game = read_pgn(&write_pgn(&game)).unwrap().remove(0);
+        let game_states = pgn_game.game_states();
+        assert_eq!(
+            game_states.last().unwrap().to_fen(),
+            game.game_state.to_fen()
+        );
+        let loaded_game = pgn_game.into_game(
+            Box::new(NoDisplay),
+            [Box::new(RandomBot), Box::new(RandomBot)],
+        );
+        assert_eq!(loaded_game.history.len(), game.history.len());
+        assert_eq!(write_pgn(&loaded_game), write_pgn(&game));
+    }
+}
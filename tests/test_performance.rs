@@ -6,6 +6,8 @@ use rust_chess::view::NoDisplay;
 mod test {
     use std::time::Instant;
 
+    use rust_chess::controllers::move_ordering::OrderingConfig;
+    use rust_chess::controllers::search::{Search, SearchConfig, SearchLimits};
     use rust_chess::controllers::{alphabeta::AlphaBetaBot, minimax::MinimaxBot};
 
     use super::*;
@@ -60,4 +62,131 @@ mod test {
         println!("average time: {} ms", total_time / n_turns);
         assert!(total_time / n_turns < 5000)
     }
+
+    const MIDDLEGAME_POSITIONS: [&str; 3] = [
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        "r2q1rk1/ppp2ppp/2np1n2/2b1p3/2B1P1b1/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 2 8",
+    ];
+
+    /// Total nodes and time to search the middlegame positions to the depth.
+    fn search_positions(config: SearchConfig, depth: u32) -> (u64, u128) {
+        let mut nodes = 0;
+        let now = Instant::now();
+        for fen in MIDDLEGAME_POSITIONS {
+            let mut search = Search::new(SearchLimits::depth(depth));
+            search.set_config(config);
+            nodes += search.run(&GameState::from_fen(fen).unwrap()).nodes;
+        }
+        (nodes, now.elapsed().as_millis())
+    }
+
+    /// Nodes needed to search a few middlegame positions to the same depth with each ordering heuristic.
+    #[test]
+    fn test_move_ordering_nodes() {
+        let no_ordering = OrderingConfig {
+            mvv_lva: false,
+            killer_moves: false,
+            history_heuristic: false,
+            countermoves: false,
+        };
+        let configs = [
+            ("hash move only", no_ordering),
+            (
+                "+ MVV-LVA",
+                OrderingConfig {
+                    mvv_lva: true,
+                    ..no_ordering
+                },
+            ),
+            (
+                "+ killer moves",
+                OrderingConfig {
+                    mvv_lva: true,
+                    killer_moves: true,
+                    ..no_ordering
+                },
+            ),
+            (
+                "+ history",
+                OrderingConfig {
+                    countermoves: false,
+                    ..OrderingConfig::default()
+                },
+            ),
+            ("+ countermoves", OrderingConfig::default()),
+        ];
+        println!("Move ordering nodes at depth 5");
+        let mut total_nodes = vec![];
+        for (name, ordering) in configs {
+            let config = SearchConfig {
+                ordering,
+                ..SearchConfig::disabled()
+            };
+            let (nodes, time) = search_positions(config, 5);
+            println!("{:>16}: {:>8} nodes {:>6} ms", name, nodes, time);
+            total_nodes.push(nodes);
+        }
+        assert!(total_nodes[4] < total_nodes[0]);
+    }
+
+    /// Effect of each search technique, compared with the search using all of them.
+    #[test]
+    fn test_search_config_nodes() {
+        let all = SearchConfig::default();
+        let configs = [
+            ("all techniques", all),
+            (
+                "no PVS",
+                SearchConfig {
+                    principal_variation_search: false,
+                    ..all
+                },
+            ),
+            (
+                "no null move",
+                SearchConfig {
+                    null_move_pruning: false,
+                    ..all
+                },
+            ),
+            (
+                "no LMR",
+                SearchConfig {
+                    late_move_reductions: false,
+                    ..all
+                },
+            ),
+            (
+                "no futility",
+                SearchConfig {
+                    futility_pruning: false,
+                    ..all
+                },
+            ),
+            (
+                "no extensions",
+                SearchConfig {
+                    check_extensions: false,
+                    ..all
+                },
+            ),
+            (
+                "no aspiration",
+                SearchConfig {
+                    aspiration_windows: false,
+                    ..all
+                },
+            ),
+            ("plain alpha-beta", SearchConfig::disabled()),
+        ];
+        println!("Search techniques nodes at depth 6");
+        let mut total_nodes = vec![];
+        for (name, config) in configs {
+            let (nodes, time) = search_positions(config, 6);
+            println!("{:>16}: {:>8} nodes {:>6} ms", name, nodes, time);
+            total_nodes.push(nodes);
+        }
+        assert!(total_nodes[0] < total_nodes[7]);
+    }
 }
@@ -0,0 +1,120 @@
+use rust_chess::model::game_state::GameState;
+use rust_chess::notation::san::{movement_to_uci, parse_movement};
+use rust_chess::rules::move_generator::generate_movements;
+use rust_chess::rules::perft::{divide, perft};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Published node counts from depth 1, for the usual test positions.
+    const PERFT_POSITIONS: [(&str, &[u64]); 6] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281, 4865609],
+        ),
+        // Kiwipete
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862, 4085603],
+        ),
+        (
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238, 674624],
+        ),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467, 422333],
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379, 2103487],
+        ),
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890, 3894594],
+        ),
+    ];
+
+    /// Fischer's random chess positions with castling rights in Shredder-FEN.
+    const PERFT_960_POSITIONS: [(&str, &[u64]); 6] = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12189, 326672],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            &[21, 807, 18002, 667366],
+        ),
+        (
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10471, 273318],
+        ),
+        (
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            &[22, 593, 13440, 382958],
+        ),
+        (
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+            &[28, 1120, 31058, 1171749],
+        ),
+        (
+            "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+            &[29, 899, 26578, 824055],
+        ),
+    ];
+
+    fn assert_perft(positions: &[(&str, &[u64])]) {
+        for (fen, counts) in positions {
+            let mut game_state = GameState::from_fen(fen).unwrap();
+            for (depth, count) in counts.iter().enumerate() {
+                assert_eq!(
+                    perft(&mut game_state, depth as u32 + 1),
+                    *count,
+                    "{} depth {}",
+                    fen,
+                    depth + 1
+                );
+            }
+            // the position is restored after counting
+            assert_eq!(game_state, GameState::from_fen(fen).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_perft() {
+        assert_perft(&PERFT_POSITIONS);
+        assert_eq!(perft(&mut GameState::new(), 0), 1);
+    }
+
+    #[test]
+    fn test_perft_960() {
+        assert_perft(&PERFT_960_POSITIONS);
+    }
+
+    #[test]
+    fn test_divide() {
+        for (fen, counts) in PERFT_POSITIONS.iter().chain(&PERFT_960_POSITIONS) {
+            let mut game_state = GameState::from_fen(fen).unwrap();
+            let divided = divide(&mut game_state, 3);
+            assert_eq!(divided.len() as u64, counts[0]);
+            assert_eq!(
+                divided.iter().map(|(_, count)| count).sum::<u64>(),
+                counts[2]
+            );
+            for (movement, count) in divided {
+                // the movements are written so that they can be read back
+                let uci = movement_to_uci(&movement, &game_state);
+                assert_eq!(parse_movement(&uci, &game_state), Ok(movement.clone()));
+                let mut next_state = game_state.clone_and_move(movement);
+                assert_eq!(count, perft(&mut next_state, 2));
+            }
+        }
+        let mut game_state = GameState::new();
+        assert!(divide(&mut game_state, 0).is_empty());
+        assert_eq!(
+            divide(&mut game_state, 1).len(),
+            generate_movements(&game_state).len()
+        );
+    }
+}
@@ -0,0 +1,250 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{normal, seconds};
+use rust_chess::controllers::alphabeta::AlphaBetaBot;
+use rust_chess::controllers::controller::{Command, Controller};
+use rust_chess::controllers::search::{time_budget, Search, SearchConfig, SearchLimits};
+use rust_chess::controllers::transposition::{Bound, Entry, TranspositionTable};
+use rust_chess::evaluation::{evaluate_material, static_exchange_evaluation, CHECK_MATE_VALUE};
+use rust_chess::model::clock::{Clock, TimeControl};
+use rust_chess::model::{game_state::GameState, movement::Movement, piece::Color};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_limit() {
+        let result = Search::new(SearchLimits::depth(3)).run(&GameState::new());
+        assert_eq!(result.depth, 3);
+        assert!(result.best_move.is_some());
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let limits = SearchLimits {
+            depth: 64,
+            time: None,
+            nodes: Some(5000),
+        };
+        let result = Search::new(limits).run(&GameState::new());
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1 && result.depth < 64);
+        assert!(result.nodes <= 5000 + 1);
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut bot = AlphaBetaBot::with_time_limit(Duration::from_millis(300));
+        let mut game_state = GameState::new();
+        let now = Instant::now();
+        let Command::Move(_) = bot.choose_command(&mut game_state, None) else {
+            panic!("The bot didn't move");
+        };
+        assert!(now.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_finds_checkmate() {
+        // back rank mate: Ra8#
+        let game_state = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let limits = SearchLimits {
+            depth: 64,
+            time: Some(seconds(10)),
+            nodes: None,
+        };
+        let result = Search::new(limits).run(&game_state);
+        assert_eq!(
+            result.best_move,
+            Some(Movement::Normal {
+                from: [0, 0],
+                to: [7, 0],
+                promotion: None,
+            })
+        );
+        // the check extension searches the replies to Ra8+, so the checkmate is found at depth 1
+        // and the search stops
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let clock = Clock::new(TimeControl::SuddenDeath { time: seconds(300) });
+        assert_eq!(time_budget(&clock, Color::White), seconds(10));
+
+        let clock = Clock::new(TimeControl::Increment {
+            time: seconds(300),
+            increment: seconds(4),
+        });
+        assert_eq!(time_budget(&clock, Color::Black), seconds(13));
+
+        let mut clock = Clock::new(TimeControl::MovesPerPeriod {
+            time: seconds(400),
+            moves: 40,
+        });
+        assert_eq!(time_budget(&clock, Color::White), seconds(10));
+        clock.moves_in_period[0] = 39;
+        clock.remaining[0] = seconds(10);
+        // never risks more than half of the remaining time
+        assert_eq!(time_budget(&clock, Color::White), seconds(5));
+    }
+
+    #[test]
+    fn test_bot_follows_the_clock() {
+        let clock = Clock::new(TimeControl::SuddenDeath { time: seconds(3) });
+        let mut bot = AlphaBetaBot::new(64);
+        let now = Instant::now();
+        bot.choose_command(&mut GameState::new(), Some(&clock));
+        assert!(now.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_transposition_table() {
+        let mut table = TranspositionTable::new(1);
+        assert!(table.len() > 1000);
+        let entry = Entry {
+            key: 12345,
+            depth: 4,
+            bound: Bound::Exact,
+            score: 7,
+            best_move: None,
+        };
+        table.store(entry.clone());
+        assert_eq!(table.probe(12345), Some(&entry));
+        assert_eq!(table.probe(54321), None);
+
+        // a shallower search doesn't replace a deeper one of the same position
+        table.store(Entry {
+            depth: 2,
+            ..entry.clone()
+        });
+        assert_eq!(table.probe(12345).unwrap().depth, 4);
+
+        // another position in the same slot replaces it
+        let other_key = 12345 + table.len() as u64;
+        table.store(Entry {
+            key: other_key,
+            depth: 1,
+            ..entry
+        });
+        assert_eq!(table.probe(12345), None);
+        assert_eq!(table.probe(other_key).unwrap().depth, 1);
+
+        let mut disabled_table = TranspositionTable::new(0);
+        disabled_table.store(Entry {
+            key: 1,
+            depth: 1,
+            bound: Bound::Lower,
+            score: 0,
+            best_move: None,
+        });
+        assert_eq!(disabled_table.probe(1), None);
+    }
+
+    #[test]
+    fn test_search_with_transposition_table() {
+        let game_state =
+            GameState::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let mut search = Search::new(SearchLimits::depth(4));
+        let first_search = search.run(&game_state);
+        // the table is kept, so searching the same position again is much faster
+        let second_search = search.run(&game_state);
+        assert_eq!(second_search.score, first_search.score);
+        assert!(second_search.nodes < first_search.nodes / 2);
+
+        search.set_hash_size(0);
+        let without_table = search.run(&game_state);
+        assert_eq!(without_table.score, first_search.score);
+    }
+
+    #[test]
+    fn test_static_exchange_evaluation() {
+        // the knight is defended by a pawn
+        let game_state = GameState::from_fen("4k3/8/4p3/3n4/8/4P3/8/3QK3 w - - 0 1").unwrap();
+        assert_eq!(
+            static_exchange_evaluation(&game_state, &normal([0, 3], [4, 3])),
+            300 - 1000
+        );
+        // a capture that is not recaptured wins the whole piece
+        let game_state = GameState::from_fen("4k3/8/8/3n4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            static_exchange_evaluation(&game_state, &normal([3, 4], [4, 3])),
+            300
+        );
+        // the rook behind the queen defends the square after the queen is taken (x-ray)
+        let game_state = GameState::from_fen("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+        assert_eq!(
+            static_exchange_evaluation(&game_state, &normal([1, 3], [4, 3])),
+            100 - 500 + 500
+        );
+        // quiet movements
+        assert_eq!(
+            static_exchange_evaluation(&GameState::new(), &normal([1, 4], [3, 4])),
+            0
+        );
+    }
+
+    #[test]
+    fn test_quiescence_search() {
+        // Qxd5 wins a knight at depth 1, but the pawn recaptures the queen
+        let game_state = GameState::from_fen("4k3/8/4p3/3n4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let mut search = Search::new(SearchLimits::depth(1));
+        search.set_evaluator(evaluate_material);
+        let result = search.run(&game_state);
+        assert_ne!(result.best_move, Some(normal([0, 3], [4, 3])));
+        assert_eq!(result.score, 1000 - 300 - 100);
+
+        // the undefended knight is captured
+        let game_state = GameState::from_fen("4k3/8/8/3n4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let result = Search::new(SearchLimits::depth(1)).run(&game_state);
+        assert_eq!(result.best_move, Some(normal([0, 3], [4, 3])));
+    }
+
+    #[test]
+    fn test_search_config() {
+        // mate in two: Re8+ Rxe8 Rxe8#
+        let game_state = GameState::from_fen("r5k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1").unwrap();
+        let all = SearchConfig::default();
+        for config in [
+            all,
+            SearchConfig::disabled(),
+            SearchConfig {
+                principal_variation_search: false,
+                ..all
+            },
+            SearchConfig {
+                null_move_pruning: false,
+                ..all
+            },
+            SearchConfig {
+                late_move_reductions: false,
+                ..all
+            },
+            SearchConfig {
+                futility_pruning: false,
+                ..all
+            },
+            SearchConfig {
+                check_extensions: false,
+                ..all
+            },
+            SearchConfig {
+                aspiration_windows: false,
+                ..all
+            },
+        ] {
+            let mut search = Search::new(SearchLimits::depth(5));
+            search.set_config(config);
+            let result = search.run(&game_state);
+            assert_eq!(result.score, CHECK_MATE_VALUE - 3, "{:?}", config);
+            let Some(Movement::Normal { to, .. }) = result.best_move else {
+                panic!("No movement found");
+            };
+            assert_eq!(to[0], 7);
+        }
+    }
+}